@@ -1,33 +1,39 @@
-use crate::{simulation};
+use std::sync::mpsc::Sender;
+
+use crate::command::SimCommand;
+use crate::sim_thread;
+use crate::simulation::SoftBodyCollection;
+use crate::snapshot::SnapshotReader;
 
 pub struct App {
-    pub window_size: [f64; 2],
-    pub mouse_pos: [f64; 2],
-    pub mouse_down: bool,
-    pub held_point_index: Option<usize>,
-    pub softbody_index: Option<usize>,
-    pub to_sim_tx: std::sync::mpsc::Sender<simulation::SoftBodyCollection>,
-    pub to_sim_rx: std::sync::mpsc::Receiver<simulation::SoftBodyCollection>,
-    pub from_sim_tx: std::sync::mpsc::Sender<simulation::SoftBodyCollection>,
-    pub from_sim_rx: std::sync::mpsc::Receiver<simulation::SoftBodyCollection>,
+    pub commands: Sender<SimCommand>,
+    pub snapshots: SnapshotReader<SoftBodyCollection>,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let window_size = [800.0, 600.0];
-        let (to_sim_tx, to_sim_rx) = std::sync::mpsc::channel();
-        let (from_sim_tx, from_sim_rx) = std::sync::mpsc::channel();
+    pub fn new(world_size: [f64; 2], softbodies: SoftBodyCollection) -> Self {
+        let (commands, snapshots) = sim_thread::spawn(softbodies, world_size);
 
         App {
-            window_size,
-            mouse_pos: [0.0, 0.0],
-            mouse_down: false,
-            held_point_index: None,
-            softbody_index: None,
-            to_sim_tx,
-            to_sim_rx,
-            from_sim_tx,
-            from_sim_rx,
+            commands,
+            snapshots,
         }
     }
-}
\ No newline at end of file
+
+    pub fn send(&self, command: SimCommand) {
+        // The simulation thread only exits once the UI drops its sender
+        self.commands.send(command).unwrap();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.send(if paused {
+            SimCommand::Pause
+        } else {
+            SimCommand::Resume
+        });
+    }
+
+    pub fn softbodies(&self) -> &SoftBodyCollection {
+        self.snapshots.latest()
+    }
+}
@@ -0,0 +1,99 @@
+// Uniform grid used to find nearby points without testing every pair, both
+// between softbodies and when merging mesh points. Entries are kept sorted by cell so iteration order, and
+// with it the order forces are accumulated in, is deterministic.
+
+type Cell = (i64, i64);
+
+#[derive(Clone)]
+pub struct SpatialGrid {
+    pub cell_size: f64,
+    // (cell, softbody index, point index), sorted by cell
+    entries: Vec<(Cell, usize, usize)>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> Self {
+        SpatialGrid {
+            cell_size: 1.0,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn cell(&self, position: [f64; 2]) -> Cell {
+        (
+            (position[0] / self.cell_size).floor() as i64,
+            (position[1] / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn rebuild(
+        &mut self,
+        cell_size: f64,
+        points: impl Iterator<Item = (usize, usize, [f64; 2])>,
+    ) {
+        self.cell_size = cell_size;
+        self.entries.clear();
+        for (body, point, position) in points {
+            let cell = self.cell(position);
+            self.entries.push((cell, body, point));
+        }
+        self.entries.sort_unstable();
+    }
+
+    fn members(&self, cell: Cell) -> &[(Cell, usize, usize)] {
+        let start = self.entries.partition_point(|e| e.0 < cell);
+        let end = self.entries.partition_point(|e| e.0 <= cell);
+        &self.entries[start..end]
+    }
+
+    /// Entries in the cell holding `position` and the eight around it, as
+    /// (softbody index, point index).
+    pub fn near(&self, position: [f64; 2]) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (x, y) = self.cell(position);
+        (-1..=1).flat_map(move |dy| {
+            (-1..=1).flat_map(move |dx| {
+                self.members((x + dx, y + dy))
+                    .iter()
+                    .map(|&(_, body, point)| (body, point))
+            })
+        })
+    }
+
+    /// Occupied cells, each listed once.
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(i, e)| *i == 0 || self.entries[i - 1].0 != e.0)
+            .map(|(_, e)| e.0)
+    }
+
+    /// Calls `f` once for every pair of points from different softbodies that
+    /// share a cell or sit in neighbouring cells.
+    pub fn for_each_pair(&self, mut f: impl FnMut((usize, usize), (usize, usize))) {
+        // Half of the 3x3 neighbourhood, so each pair of cells is visited once
+        const NEIGHBOURS: [Cell; 4] = [(1, 0), (1, 1), (0, 1), (-1, 1)];
+
+        for cell in self.cells() {
+            let members = self.members(cell);
+            for (i, a) in members.iter().enumerate() {
+                for b in &members[i + 1..] {
+                    if a.1 != b.1 {
+                        f((a.1, a.2), (b.1, b.2));
+                    }
+                }
+            }
+
+            for (dx, dy) in NEIGHBOURS {
+                let others = self.members((cell.0 + dx, cell.1 + dy));
+                for a in members {
+                    for b in others {
+                        if a.1 != b.1 {
+                            f((a.1, a.2), (b.1, b.2));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
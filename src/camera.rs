@@ -0,0 +1,108 @@
+use piston_window::{Context, Transformed};
+
+use crate::simulation::{self, SoftBodyCollection};
+
+const MIN_ZOOM: f64 = 0.1;
+const MAX_ZOOM: f64 = 10.0;
+
+pub struct Camera {
+    pub center: [f64; 2],
+    pub zoom: f64,
+    pub viewport: [f64; 2],
+    pub follow: Option<usize>,
+    home: [f64; 2],
+}
+
+impl Camera {
+    /// Creates a camera looking at `home`, which is also where `reset` returns to.
+    pub fn new(viewport: [f64; 2], home: [f64; 2]) -> Self {
+        Camera {
+            center: home,
+            zoom: 1.0,
+            viewport,
+            follow: None,
+            home,
+        }
+    }
+
+    pub fn screen_to_world(&self, pos: [f64; 2]) -> [f64; 2] {
+        [
+            (pos[0] - self.viewport[0] / 2.0) / self.zoom + self.center[0],
+            (pos[1] - self.viewport[1] / 2.0) / self.zoom + self.center[1],
+        ]
+    }
+
+    /// Returns a context that draws in world coordinates.
+    pub fn apply(&self, c: Context) -> Context {
+        c.trans(self.viewport[0] / 2.0, self.viewport[1] / 2.0)
+            .zoom(self.zoom)
+            .trans(-self.center[0], -self.center[1])
+    }
+
+    /// Zooms by `factor` while keeping the world point under `screen_pos` fixed.
+    pub fn zoom_at(&mut self, screen_pos: [f64; 2], factor: f64) {
+        let anchor = self.screen_to_world(screen_pos);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let moved = self.screen_to_world(screen_pos);
+        self.center[0] += anchor[0] - moved[0];
+        self.center[1] += anchor[1] - moved[1];
+    }
+
+    pub fn pan(&mut self, screen_delta: [f64; 2]) {
+        self.center[0] -= screen_delta[0] / self.zoom;
+        self.center[1] -= screen_delta[1] / self.zoom;
+        self.follow = None;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Camera::new(self.viewport, self.home);
+    }
+
+    /// Keeps following the same softbody after softbody `index` is removed,
+    /// or stops if it was that one.
+    pub fn softbody_removed(&mut self, index: usize) {
+        self.follow = self
+            .follow
+            .and_then(|body| simulation::renumber(body, index));
+    }
+
+    /// Re-centers on the followed softbody, dropping it if it no longer exists.
+    pub fn update(&mut self, softbodies: &SoftBodyCollection) {
+        if let Some(index) = self.follow {
+            match softbodies.softbodies.get(index) {
+                Some(softbody) => self.center = softbody.centroid(),
+                None => self.follow = None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zooming_keeps_the_point_under_the_cursor() {
+        let mut camera = Camera::new([800.0, 600.0], [400.0, 300.0]);
+        assert_eq!(camera.screen_to_world([400.0, 300.0]), [400.0, 300.0]);
+        let cursor = [100.0, 500.0];
+        let before = camera.screen_to_world(cursor);
+        camera.zoom_at(cursor, 2.5);
+        assert_eq!(camera.zoom, 2.5);
+        let after = camera.screen_to_world(cursor);
+        assert!((after[0] - before[0]).abs() < 1e-9 && (after[1] - before[1]).abs() < 1e-9);
+
+        camera.zoom_at(cursor, 1000.0);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn panning_moves_by_screen_distance_and_stops_following() {
+        let mut camera = Camera::new([800.0, 600.0], [0.0, 0.0]);
+        camera.zoom = 2.0;
+        camera.follow = Some(0);
+        camera.pan([20.0, -10.0]);
+        assert_eq!(camera.center, [-10.0, 5.0]);
+        assert_eq!(camera.follow, None);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::geometry;
+
+/// Fixed polygon that softbody points can't enter, e.g. level geometry.
+#[derive(Clone, PartialEq)]
+pub struct Collider {
+    pub outline: Vec<[f64; 2]>,
+    // [min_x, min_y, max_x, max_y], to skip the polygon test for far points
+    aabb: [f64; 4],
+}
+
+impl Collider {
+    pub fn new(outline: Vec<[f64; 2]>) -> Self {
+        let aabb = outline.iter().fold(
+            [f64::MAX, f64::MAX, f64::MIN, f64::MIN],
+            |[min_x, min_y, max_x, max_y], p| {
+                [
+                    min_x.min(p[0]),
+                    min_y.min(p[1]),
+                    max_x.max(p[0]),
+                    max_y.max(p[1]),
+                ]
+            },
+        );
+        Collider { outline, aabb }
+    }
+
+    /// If `p` is inside, returns the closest point on the surface and the
+    /// outward normal there.
+    pub fn push_out(&self, p: [f64; 2]) -> Option<([f64; 2], [f64; 2])> {
+        let [min_x, min_y, max_x, max_y] = self.aabb;
+        if p[0] < min_x || p[0] > max_x || p[1] < min_y || p[1] > max_y {
+            return None;
+        }
+        if !geometry::contains(&self.outline, p) {
+            return None;
+        }
+        Some(self.closest(p))
+    }
+
+    /// The closest point on the surface to `p`, inside or out, and the outward
+    /// normal of the edge it lies on.
+    pub fn closest(&self, p: [f64; 2]) -> ([f64; 2], [f64; 2]) {
+        let n = self.outline.len();
+        let side = geometry::signed_area(&self.outline).signum();
+        let (a, b) = (0..n)
+            .map(|i| (self.outline[i], self.outline[(i + 1) % n]))
+            .min_by(|&(a1, b1), &(a2, b2)| {
+                geometry::distance_to_segment(p, a1, b1)
+                    .total_cmp(&geometry::distance_to_segment(p, a2, b2))
+            })
+            .unwrap_or((p, p));
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = dx.hypot(dy).max(f64::EPSILON);
+        (
+            geometry::closest_on_segment(p, a, b),
+            [side * dy / len, -side * dx / len],
+        )
+    }
+
+    /// Whether the bounding box of the collider overlaps `aabb`, given in the
+    /// same [min_x, min_y, max_x, max_y] form.
+    pub fn overlaps(&self, aabb: [f64; 4]) -> bool {
+        self.aabb[0] <= aabb[2]
+            && aabb[0] <= self.aabb[2]
+            && self.aabb[1] <= aabb[3]
+            && aabb[1] <= self.aabb[3]
+    }
+}
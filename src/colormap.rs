@@ -0,0 +1,60 @@
+// Colormaps for mapping scalar fields onto springs.
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+fn sample(stops: &[[f32; 3]], t: f64) -> [f32; 4] {
+    let t = t.clamp(0.0, 1.0) as f32 * (stops.len() - 1) as f32;
+    let i = (t as usize).min(stops.len() - 2);
+    let [r, g, b] = lerp(stops[i], stops[i + 1], t - i as f32);
+    [r, g, b, 1.0]
+}
+
+/// Perceptually uniform sequential map for `t` in 0..=1.
+pub fn viridis(t: f64) -> [f32; 4] {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.267, 0.005, 0.329],
+        [0.230, 0.322, 0.546],
+        [0.128, 0.567, 0.551],
+        [0.369, 0.789, 0.383],
+        [0.993, 0.906, 0.144],
+    ];
+    sample(&STOPS, t)
+}
+
+/// Blue-white-red map for `t` in -1..=1, white at zero.
+pub fn diverging(t: f64) -> [f32; 4] {
+    const STOPS: [[f32; 3]; 3] = [
+        [0.230, 0.299, 0.754],
+        [0.95, 0.95, 0.95],
+        [0.706, 0.016, 0.150],
+    ];
+    sample(&STOPS, (t + 1.0) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_hit_their_end_stops_and_clamp_beyond() {
+        let close = |a: [f32; 4], b: [f32; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-6);
+        assert!(close(viridis(0.0), [0.267, 0.005, 0.329, 1.0]));
+        assert!(close(viridis(1.0), [0.993, 0.906, 0.144, 1.0]));
+        assert_eq!(viridis(-3.0), viridis(0.0));
+        assert_eq!(viridis(7.0), viridis(1.0));
+        assert_eq!(diverging(0.0), [0.95, 0.95, 0.95, 1.0]);
+        assert_eq!(diverging(-2.0), diverging(-1.0));
+    }
+
+    #[test]
+    fn sampling_blends_between_stops() {
+        let [r, g, b, _] = sample(&[[0.0, 0.0, 0.0], [1.0, 0.5, 0.0]], 0.5);
+        assert_eq!([r, g, b], [0.5, 0.25, 0.0]);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::field::Field;
+use crate::fluid::Fluid;
+use crate::rigid::RigidBody;
+use crate::simulation::{Joint, SoftBody, SoftBodyCollection};
+
+/// Everything the UI can ask of the simulation thread. Commands are queued and
+/// applied in order at the start of the next step, never in the middle of one.
+#[derive(Clone)]
+pub enum SimCommand {
+    Spawn(SoftBody),
+    SpawnRigid(RigidBody),
+    Remove(usize),
+    RemoveRigid(usize),
+    AddJoint(Joint),
+    AddField(Field),
+    // switches the field at an index on or off without removing it
+    SetFieldEnabled(usize, bool),
+    ClearFields,
+    AddFluid(Fluid),
+    ClearFluids,
+    Drag {
+        body: usize,
+        point: usize,
+        target: [f64; 2],
+    },
+    Release,
+    Pause,
+    Resume,
+    Step(u32),
+    SetParam(SimParam),
+    LoadScene(SoftBodyCollection),
+    Reset,
+}
+
+#[derive(Clone, Copy)]
+pub enum SimParam {
+    Gravity([f64; 2]),
+    Wind([f64; 2]),
+    TimeScale(f64),
+    DragStiffness(f64),
+    DragDamping(f64),
+    Stiffness(f64),
+    Damping(f64),
+    Friction(f64),
+    AreaStiffness(f64),
+    Plasticity { yield_strain: f64, creep: f64 },
+}
@@ -0,0 +1,174 @@
+use std::fs;
+
+use crate::svg::SvgOptions;
+
+/// Viewer settings, read from an optional `--config <file>` of `key = value`
+/// lines and then overridden by `--key value` command line flags.
+pub struct Config {
+    pub window_size: [f64; 2],
+    pub world_size: [f64; 2],
+    pub vsync: bool,
+    pub samples: u8,
+    pub fullscreen: bool,
+    // SVG file to build the starting scene from, instead of the default bodies
+    pub scene: Option<String>,
+    pub svg: SvgOptions,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            window_size: [800.0, 600.0],
+            world_size: [800.0, 600.0],
+            vsync: true,
+            samples: 0,
+            fullscreen: false,
+            scene: None,
+            svg: SvgOptions {
+                scale: 1.0,
+                tolerance: 0.5,
+                spacing: 20.0,
+                static_layer: "static".to_string(),
+                fluid_layer: "fluid".to_string(),
+            },
+        }
+    }
+
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config::new();
+        let mut flags = Vec::new();
+
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(format!("unexpected argument '{}'", arg));
+            };
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                // A flag with no value is a switch, e.g. `--fullscreen`
+                None => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(value) => (flag.to_string(), value),
+                    None => (flag.to_string(), "true".to_string()),
+                },
+            };
+            flags.push((key, value));
+        }
+
+        // Apply the config file first so flags on the command line win
+        if let Some((_, path)) = flags.iter().find(|(key, _)| key == "config") {
+            let contents = fs::read_to_string(path)
+                .map_err(|e| format!("could not read config '{}': {}", path, e))?;
+            for line in contents.lines() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected 'key = value' in config, got '{}'", line))?;
+                config.set(key.trim(), value.trim())?;
+            }
+        }
+
+        for (key, value) in &flags {
+            if key != "config" {
+                config.set(key, value)?;
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "width" => self.window_size[0] = positive(key, value)?,
+            "height" => self.window_size[1] = positive(key, value)?,
+            "world-width" => self.world_size[0] = positive(key, value)?,
+            "world-height" => self.world_size[1] = positive(key, value)?,
+            "vsync" => self.vsync = parse(key, value)?,
+            "msaa" => self.samples = parse(key, value)?,
+            "fullscreen" => self.fullscreen = parse(key, value)?,
+            "svg" => self.scene = Some(value.to_string()),
+            "svg-scale" => self.svg.scale = positive(key, value)?,
+            "svg-tolerance" => self.svg.tolerance = positive(key, value)?,
+            "svg-spacing" => self.svg.spacing = positive(key, value)?,
+            "static-layer" => self.svg.static_layer = value.to_string(),
+            "fluid-layer" => self.svg.fluid_layer = value.to_string(),
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, key))
+}
+
+fn positive(key: &str, value: &str) -> Result<f64, String> {
+    let number: f64 = parse(key, value)?;
+    if number > 0.0 && number.is_finite() {
+        Ok(number)
+    } else {
+        Err(format!("'{}' must be greater than 0, got '{}'", key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(args: &[&str]) -> Result<Config, String> {
+        Config::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_override_defaults() {
+        let config = config(&["--width", "1024", "--fullscreen", "--svg-spacing=12.5"]).unwrap();
+        assert_eq!(config.window_size, [1024.0, 600.0]);
+        assert!(config.fullscreen);
+        assert_eq!(config.svg.spacing, 12.5);
+    }
+
+    #[test]
+    fn window_and_world_sizes_must_be_positive() {
+        for key in ["--width", "--height", "--world-width", "--world-height"] {
+            assert!(config(&[key, "0"]).is_err());
+            assert!(config(&[key, "-600"]).is_err());
+            assert!(config(&[key, "inf"]).is_err());
+        }
+    }
+
+    #[test]
+    fn svg_sizes_must_be_positive() {
+        for key in ["--svg-scale", "--svg-tolerance", "--svg-spacing"] {
+            assert!(config(&[key, "0"]).is_err());
+            assert!(config(&[key, "-1"]).is_err());
+        }
+    }
+
+    #[test]
+    fn flags_win_over_the_config_file() {
+        let path = std::env::temp_dir().join(format!("softbody-config-{}", std::process::id()));
+        fs::write(
+            &path,
+            "# window\nwidth = 1280\nheight = 720 # wide\n\nvsync = false\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let config = config(&["--height", "900", "--config", path]);
+        fs::remove_file(path).unwrap();
+        let config = config.unwrap();
+        assert_eq!(config.window_size, [1280.0, 900.0]);
+        assert!(!config.vsync);
+    }
+
+    #[test]
+    fn bad_settings_are_reported() {
+        assert!(config(&["--width", "wide"]).is_err());
+        assert!(config(&["--colour", "red"]).is_err());
+        assert!(config(&["width"]).is_err());
+        assert!(config(&["--config", "/nonexistent/softbody.conf"]).is_err());
+    }
+}
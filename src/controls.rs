@@ -0,0 +1,375 @@
+use crate::app::App;
+use crate::command::{SimCommand, SimParam};
+use crate::field::Region;
+use crate::palette::{FieldKind, Palette, Shape};
+use crate::render::{HeatmapMode, RenderSettings};
+use crate::sim_thread::{
+    DEFAULT_DRAG_DAMPING, DEFAULT_DRAG_STIFFNESS, MAX_TIME_SCALE, MIN_TIME_SCALE,
+};
+use crate::simulation::{self, JointKind, MATERIALS};
+use crate::ui::{Anchor, Ui};
+
+// Presets cycled with the -/= keys, from slow motion up to fast-forward
+const TIME_SCALES: [f64; 7] = [0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 4.0];
+
+// Yield strain used when plasticity is turned up from the panel
+const YIELD_STRAIN: f64 = 0.1;
+
+// Degrees gravity turns per arrow key press
+const GRAVITY_STEP: f64 = 15.0;
+
+/// How the direction of gravity is steered from the viewer.
+#[derive(Clone, Copy, PartialEq)]
+pub enum GravityMode {
+    // only the panel's angle slider moves it
+    Fixed,
+    // left and right arrows turn it
+    ArrowKeys,
+    // it points from the middle of the window toward the cursor
+    FollowMouse,
+}
+
+impl GravityMode {
+    pub const ALL: [GravityMode; 3] = [
+        GravityMode::Fixed,
+        GravityMode::ArrowKeys,
+        GravityMode::FollowMouse,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GravityMode::Fixed => "Fixed",
+            GravityMode::ArrowKeys => "Arrow keys",
+            GravityMode::FollowMouse => "Follow mouse",
+        }
+    }
+}
+
+/// The viewer's copy of the live simulation settings. The simulation thread owns
+/// the real values, so every change here is also sent to it as a command.
+pub struct Controls {
+    pub paused: bool,
+    pub time_scale: f64,
+    // strength and direction of gravity, in degrees clockwise from the +x
+    // axis, so 90 points straight down the screen
+    pub gravity: f64,
+    pub gravity_angle: f64,
+    pub gravity_mode: GravityMode,
+    pub wind: f64,
+    pub stiffness: f64,
+    pub damping: f64,
+    pub friction: f64,
+    pub area_stiffness: f64,
+    pub creep: f64,
+    // how hard the mouse pulls on a dragged point
+    pub drag_stiffness: f64,
+    pub drag_damping: f64,
+    pub palette: Palette,
+}
+
+impl Controls {
+    pub fn new() -> Self {
+        Controls {
+            paused: false,
+            time_scale: 1.0,
+            gravity: simulation::DEFAULT_GRAVITY[0].hypot(simulation::DEFAULT_GRAVITY[1]),
+            gravity_angle: simulation::DEFAULT_GRAVITY[1]
+                .atan2(simulation::DEFAULT_GRAVITY[0])
+                .to_degrees(),
+            gravity_mode: GravityMode::Fixed,
+            wind: 0.0,
+            stiffness: simulation::DEFAULT_STIFFNESS,
+            damping: simulation::DEFAULT_DAMPING,
+            friction: simulation::DEFAULT_FRICTION,
+            area_stiffness: simulation::DEFAULT_AREA_STIFFNESS,
+            creep: 0.0,
+            drag_stiffness: DEFAULT_DRAG_STIFFNESS,
+            drag_damping: DEFAULT_DRAG_DAMPING,
+            palette: Palette::new(),
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool, app: &App) {
+        self.paused = paused;
+        app.set_paused(paused);
+    }
+
+    pub fn step(&mut self, steps: u32, app: &App) {
+        self.set_paused(true, app);
+        app.send(SimCommand::Step(steps));
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64, app: &App) {
+        self.time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        app.send(SimCommand::SetParam(SimParam::TimeScale(self.time_scale)));
+    }
+
+    pub fn slower(&mut self, app: &App) {
+        let preset = TIME_SCALES
+            .iter()
+            .rev()
+            .find(|&&s| s < self.time_scale)
+            .unwrap_or(&TIME_SCALES[0]);
+        self.set_time_scale(*preset, app);
+    }
+
+    pub fn faster(&mut self, app: &App) {
+        let preset = TIME_SCALES
+            .iter()
+            .find(|&&s| s > self.time_scale)
+            .unwrap_or(&TIME_SCALES[TIME_SCALES.len() - 1]);
+        self.set_time_scale(*preset, app);
+    }
+
+    fn send_gravity(&self, app: &App) {
+        let (sin, cos) = self.gravity_angle.to_radians().sin_cos();
+        app.send(SimCommand::SetParam(SimParam::Gravity([
+            self.gravity * cos,
+            self.gravity * sin,
+        ])));
+    }
+
+    /// Points gravity `angle` degrees clockwise from the +x axis.
+    pub fn set_gravity_angle(&mut self, angle: f64, app: &App) {
+        self.gravity_angle = (angle + 180.0).rem_euclid(360.0) - 180.0;
+        self.send_gravity(app);
+    }
+
+    /// Turns gravity one step clockwise, or anticlockwise for a negative
+    /// `steps`, if the arrow keys are steering it.
+    pub fn turn_gravity(&mut self, steps: f64, app: &App) {
+        if self.gravity_mode == GravityMode::ArrowKeys {
+            self.set_gravity_angle(self.gravity_angle + steps * GRAVITY_STEP, app);
+        }
+    }
+
+    /// Points gravity along `direction`, if it's following the mouse.
+    pub fn follow_mouse(&mut self, direction: [f64; 2], app: &App) {
+        if self.gravity_mode == GravityMode::FollowMouse && direction != [0.0, 0.0] {
+            self.set_gravity_angle(direction[1].atan2(direction[0]).to_degrees(), app);
+        }
+    }
+
+    /// Declares the control panels for this frame and sends any changes.
+    pub fn show(&mut self, ui: &mut Ui, app: &App, render_settings: &mut RenderSettings) {
+        let palette = &mut self.palette;
+        ui.begin_panel(Anchor::TopLeft, [10.0, 10.0], 180.0);
+        let shapes = Shape::ALL.map(|shape| shape.label());
+        let mut shape = Shape::ALL
+            .iter()
+            .position(|&shape| shape == palette.shape)
+            .unwrap_or(0);
+        if ui.dropdown("Shape", &mut shape, &shapes) {
+            palette.shape = Shape::ALL[shape];
+        }
+        let materials = MATERIALS.map(|material| material.name);
+        ui.dropdown("Material", &mut palette.material, &materials);
+        ui.checkbox("Finite elements", &mut palette.fem);
+        ui.checkbox("Rigid", &mut palette.rigid);
+        if palette.rigid {
+            ui.checkbox("Fixed", &mut palette.fixed);
+        }
+        ui.checkbox("Aerodynamic", &mut palette.aerodynamic);
+        ui.slider("Gravity scale", &mut palette.gravity_scale, -1.0, 2.0);
+        ui.slider("Size", &mut palette.size, 20.0, 300.0);
+        ui.slider("Resolution", &mut palette.resolution, 1.0, 12.0);
+        let joints = JointKind::ALL.map(|kind| kind.label());
+        let mut joint = JointKind::ALL
+            .iter()
+            .position(|&kind| kind == palette.joint)
+            .unwrap_or(0);
+        if ui.dropdown("Joint", &mut joint, &joints) {
+            palette.joint = JointKind::ALL[joint];
+        }
+        let fields = FieldKind::ALL.map(|kind| kind.label());
+        let mut field = FieldKind::ALL
+            .iter()
+            .position(|&kind| kind == palette.field)
+            .unwrap_or(0);
+        if ui.dropdown("Field", &mut field, &fields) {
+            palette.field = FieldKind::ALL[field];
+        }
+        ui.checkbox("Field everywhere", &mut palette.field_everywhere);
+        // One switch per field in the scene, to try it with and without
+        for (i, field) in app.softbodies().fields.iter().enumerate() {
+            let place = match field.region {
+                Region::Everywhere => "everywhere".to_string(),
+                Region::Circle { center, .. } => format!("at {:.0}, {:.0}", center[0], center[1]),
+                Region::Rect([min_x, min_y, max_x, max_y]) => format!(
+                    "at {:.0}, {:.0}",
+                    (min_x + max_x) / 2.0,
+                    (min_y + max_y) / 2.0
+                ),
+            };
+            let mut enabled = field.enabled;
+            if ui.checkbox(&format!("Field {} {}", i + 1, place), &mut enabled) {
+                app.send(SimCommand::SetFieldEnabled(i, enabled));
+            }
+        }
+        if ui.button("Clear fields") {
+            app.send(SimCommand::ClearFields);
+        }
+        if ui.button("Drain water") {
+            app.send(SimCommand::ClearFluids);
+        }
+        if ui.button("Spawn") {
+            app.send(palette.spawn([200.0, 100.0], [0.0, 0.0]));
+        }
+        if ui.checkbox("Click to place", &mut palette.placing) && palette.placing {
+            palette.drawing = false;
+        }
+        if ui.checkbox("Draw outline", &mut palette.drawing) {
+            palette.outline.clear();
+            if palette.drawing {
+                palette.placing = false;
+            }
+        }
+        if palette.drawing && palette.outline.len() >= 3 && ui.button("Finish outline") {
+            match palette.finish_outline() {
+                Ok(command) => app.send(command),
+                Err(e) => eprintln!("error: {}", e),
+            }
+        }
+        ui.end_panel();
+
+        ui.begin_panel(Anchor::TopRight, [230.0, 10.0], 220.0);
+        ui.label(if self.paused { "Paused" } else { "Running" });
+        if ui.button(if self.paused { "Resume" } else { "Pause" }) {
+            self.set_paused(!self.paused, app);
+        }
+        if ui.button("Step") {
+            self.step(1, app);
+        }
+        if ui.button("Reset") {
+            app.send(SimCommand::Reset);
+        }
+
+        let mut time_scale = self.time_scale;
+        if ui.slider(
+            "Time scale",
+            &mut time_scale,
+            MIN_TIME_SCALE,
+            MAX_TIME_SCALE,
+        ) {
+            self.set_time_scale(time_scale, app);
+        }
+        if ui.slider("Gravity", &mut self.gravity, 0.0, 0.5) {
+            self.send_gravity(app);
+        }
+        let mut angle = self.gravity_angle;
+        if ui.slider("Gravity angle", &mut angle, -180.0, 180.0) {
+            self.set_gravity_angle(angle, app);
+        }
+        let modes = GravityMode::ALL.map(|mode| mode.label());
+        let mut mode = GravityMode::ALL
+            .iter()
+            .position(|&mode| mode == self.gravity_mode)
+            .unwrap_or(0);
+        if ui.dropdown("Gravity mode", &mut mode, &modes) {
+            self.gravity_mode = GravityMode::ALL[mode];
+        }
+        if ui.slider("Wind", &mut self.wind, -5.0, 5.0) {
+            app.send(SimCommand::SetParam(SimParam::Wind([self.wind, 0.0])));
+        }
+        if ui.slider("Stiffness", &mut self.stiffness, 0.05, 2.0) {
+            app.send(SimCommand::SetParam(SimParam::Stiffness(self.stiffness)));
+        }
+        if ui.slider("Damping", &mut self.damping, 0.05, 1.0) {
+            app.send(SimCommand::SetParam(SimParam::Damping(self.damping)));
+        }
+        if ui.slider("Friction", &mut self.friction, 0.9, 1.0) {
+            app.send(SimCommand::SetParam(SimParam::Friction(self.friction)));
+        }
+        if ui.slider("Area stiffness", &mut self.area_stiffness, 0.0, 0.5) {
+            app.send(SimCommand::SetParam(SimParam::AreaStiffness(
+                self.area_stiffness,
+            )));
+        }
+        if ui.slider("Plastic creep", &mut self.creep, 0.0, 0.1) {
+            app.send(SimCommand::SetParam(SimParam::Plasticity {
+                yield_strain: YIELD_STRAIN,
+                creep: self.creep,
+            }));
+        }
+        if ui.slider("Drag stiffness", &mut self.drag_stiffness, 0.05, 1.0) {
+            app.send(SimCommand::SetParam(SimParam::DragStiffness(
+                self.drag_stiffness,
+            )));
+        }
+        if ui.slider("Drag damping", &mut self.drag_damping, 0.0, 1.0) {
+            app.send(SimCommand::SetParam(SimParam::DragDamping(
+                self.drag_damping,
+            )));
+        }
+
+        ui.checkbox("Fill", &mut render_settings.fill);
+        ui.checkbox("Outline", &mut render_settings.outline);
+        let mut smooth = render_settings.smoothing > 0;
+        if ui.checkbox("Smooth", &mut smooth) {
+            render_settings.smoothing = if smooth { 2 } else { 0 };
+        }
+        ui.checkbox("Wireframe", &mut render_settings.wireframe);
+
+        let labels = HeatmapMode::ALL.map(|mode| mode.label());
+        let mut selected = HeatmapMode::ALL
+            .iter()
+            .position(|&mode| mode == render_settings.heatmap)
+            .unwrap_or(0);
+        if ui.dropdown("Heatmap", &mut selected, &labels) {
+            render_settings.set_heatmap(HeatmapMode::ALL[selected]);
+        }
+        ui.end_panel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SoftBodyCollection;
+    use crate::snapshot;
+    use std::sync::mpsc::{self, Receiver};
+
+    fn app() -> (App, Receiver<SimCommand>) {
+        let (commands, received) = mpsc::channel();
+        let (_, snapshots) = snapshot::channel(&SoftBodyCollection::new());
+        (
+            App {
+                commands,
+                snapshots,
+            },
+            received,
+        )
+    }
+
+    #[test]
+    fn arrow_keys_turn_gravity_only_when_steering() {
+        let (app, received) = app();
+        let mut controls = Controls::new();
+        controls.turn_gravity(1.0, &app);
+        assert!(received.try_recv().is_err());
+
+        controls.gravity_mode = GravityMode::ArrowKeys;
+        controls.gravity_angle = 170.0;
+        controls.turn_gravity(1.0, &app);
+        // Past 180 it wraps round to the negative side
+        assert_eq!(controls.gravity_angle, -175.0);
+        let Ok(SimCommand::SetParam(SimParam::Gravity(gravity))) = received.try_recv() else {
+            panic!("turning gravity should send it to the simulation");
+        };
+        let angle = gravity[1].atan2(gravity[0]).to_degrees();
+        assert!((angle + 175.0).abs() < 1e-9);
+        assert!((gravity[0].hypot(gravity[1]) - controls.gravity).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gravity_follows_the_mouse_direction() {
+        let (app, _received) = app();
+        let mut controls = Controls::new();
+        controls.gravity_mode = GravityMode::FollowMouse;
+        controls.follow_mouse([0.0, -50.0], &app);
+        assert_eq!(controls.gravity_angle, -90.0);
+        // The middle of the window has no direction, so gravity stays put
+        controls.follow_mouse([0.0, 0.0], &app);
+        assert_eq!(controls.gravity_angle, -90.0);
+    }
+}
@@ -0,0 +1,187 @@
+// Constant-strain triangle elements with co-rotational linear elasticity. Each
+// element measures how its triangle has been deformed since rest, takes out the
+// rotation, and pushes the corners back with the stress linear elasticity gives
+// for what's left. Unlike springs, the response doesn't depend on how the mesh
+// happens to be laid out.
+
+type Matrix = [[f64; 2]; 2];
+
+// viscous stress per unit strain rate, relative to the shear modulus
+const VISCOSITY: f64 = 1.0;
+
+#[derive(Clone, PartialEq)]
+pub struct FemElement {
+    pub corners: [usize; 3],
+    // inverse of the rest edge matrix, mapping deformed edges to the deformation gradient
+    rest_inverse: Matrix,
+    pub rest_area: f64,
+    // Lamé parameters
+    mu: f64,
+    lambda: f64,
+}
+
+fn multiply(a: Matrix, b: Matrix) -> Matrix {
+    [
+        [
+            a[0][0] * b[0][0] + a[0][1] * b[1][0],
+            a[0][0] * b[0][1] + a[0][1] * b[1][1],
+        ],
+        [
+            a[1][0] * b[0][0] + a[1][1] * b[1][0],
+            a[1][0] * b[0][1] + a[1][1] * b[1][1],
+        ],
+    ]
+}
+
+fn transpose(m: Matrix) -> Matrix {
+    [[m[0][0], m[1][0]], [m[0][1], m[1][1]]]
+}
+
+/// Columns are the edges from the first corner to the other two.
+fn edge_matrix(p: [[f64; 2]; 3]) -> Matrix {
+    [
+        [p[1][0] - p[0][0], p[2][0] - p[0][0]],
+        [p[1][1] - p[0][1], p[2][1] - p[0][1]],
+    ]
+}
+
+/// Rotation part of the polar decomposition of `f`.
+fn rotation(f: Matrix) -> Matrix {
+    let angle = (f[1][0] - f[0][1]).atan2(f[0][0] + f[1][1]);
+    let (sin, cos) = angle.sin_cos();
+    [[cos, -sin], [sin, cos]]
+}
+
+/// Symmetric part of `m`.
+fn symmetric(m: Matrix) -> Matrix {
+    let off = (m[0][1] + m[1][0]) / 2.0;
+    [[m[0][0], off], [off, m[1][1]]]
+}
+
+impl FemElement {
+    /// Builds an element at rest in its current shape, or `None` for a
+    /// degenerate triangle.
+    pub fn new(
+        corners: [usize; 3],
+        positions: [[f64; 2]; 3],
+        youngs_modulus: f64,
+        poisson_ratio: f64,
+    ) -> Option<Self> {
+        let rest = edge_matrix(positions);
+        let det = rest[0][0] * rest[1][1] - rest[0][1] * rest[1][0];
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        let rest_inverse = [
+            [rest[1][1] / det, -rest[0][1] / det],
+            [-rest[1][0] / det, rest[0][0] / det],
+        ];
+
+        // Plane strain; a ratio of 0.5 would make the material incompressible
+        let nu = poisson_ratio.clamp(0.0, 0.45);
+        Some(FemElement {
+            corners,
+            rest_inverse,
+            rest_area: det.abs() / 2.0,
+            mu: youngs_modulus / (2.0 * (1.0 + nu)),
+            lambda: youngs_modulus * nu / ((1.0 + nu) * (1.0 - 2.0 * nu)),
+        })
+    }
+
+    /// How hard each corner is pulled back per unit of displacement, counting
+    /// the viscous term as well. An explicit step of one frame stays stable
+    /// while a corner's mass is at least this.
+    pub fn corner_stiffness(&self) -> [f64; 3] {
+        // Gradients of the corners' shape functions over the rest triangle
+        let g1 = self.rest_inverse[0];
+        let g2 = self.rest_inverse[1];
+        let g0 = [-g1[0] - g2[0], -g1[1] - g2[1]];
+        let modulus = 2.0 * self.mu * (1.0 + VISCOSITY) + self.lambda;
+        [g0, g1, g2].map(|g| modulus * self.rest_area * (g[0] * g[0] + g[1] * g[1]))
+    }
+
+    /// Size of the strain, as the norm of the rotation-free strain tensor.
+    pub fn strain(&self, positions: [[f64; 2]; 3]) -> f64 {
+        let f = multiply(edge_matrix(positions), self.rest_inverse);
+        let s = symmetric(multiply(transpose(rotation(f)), f));
+        let e = [[s[0][0] - 1.0, s[0][1]], [s[1][0], s[1][1] - 1.0]];
+        (e[0][0].powi(2) + 2.0 * e[0][1].powi(2) + e[1][1].powi(2)).sqrt()
+    }
+
+    /// Elastic and viscous forces on the three corners.
+    pub fn forces(&self, positions: [[f64; 2]; 3], velocities: [[f64; 2]; 3]) -> [[f64; 2]; 3] {
+        let f = multiply(edge_matrix(positions), self.rest_inverse);
+        let f_rate = multiply(edge_matrix(velocities), self.rest_inverse);
+        let r = rotation(f);
+        let rt = transpose(r);
+
+        // Strain and strain rate with the element's rotation taken out
+        let s = symmetric(multiply(rt, f));
+        let strain = [[s[0][0] - 1.0, s[0][1]], [s[1][0], s[1][1] - 1.0]];
+        let strain_rate = symmetric(multiply(rt, f_rate));
+
+        let trace = strain[0][0] + strain[1][1];
+        let viscosity = VISCOSITY * self.mu;
+        let mut stress = [[0.0; 2]; 2];
+        for i in 0..2 {
+            for j in 0..2 {
+                stress[i][j] = 2.0 * self.mu * strain[i][j] + 2.0 * viscosity * strain_rate[i][j];
+            }
+            stress[i][i] += self.lambda * trace;
+        }
+
+        // First Piola-Kirchhoff stress back in world orientation, spread over
+        // the rest area onto the two edge corners
+        let h = multiply(multiply(r, stress), transpose(self.rest_inverse));
+        let f1 = [-self.rest_area * h[0][0], -self.rest_area * h[1][0]];
+        let f2 = [-self.rest_area * h[0][1], -self.rest_area * h[1][1]];
+        [[-f1[0] - f2[0], -f1[1] - f2[1]], f1, f2]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REST: [[f64; 2]; 3] = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]];
+    const STILL: [[f64; 2]; 3] = [[0.0; 2]; 3];
+
+    fn element() -> FemElement {
+        FemElement::new([0, 1, 2], REST, 4.0, 0.3).unwrap()
+    }
+
+    fn length(v: [f64; 2]) -> f64 {
+        v[0].hypot(v[1])
+    }
+
+    #[test]
+    fn rest_and_rotated_shapes_are_unstressed() {
+        let element = element();
+        let (sin, cos) = 1.2_f64.sin_cos();
+        let rotated = REST.map(|p| [p[0] * cos - p[1] * sin + 5.0, p[0] * sin + p[1] * cos]);
+        for shape in [REST, rotated] {
+            assert!(element.strain(shape) < 1e-9);
+            for force in element.forces(shape, STILL) {
+                assert!(length(force) < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn stretched_element_pulls_back_without_net_force() {
+        let element = element();
+        let stretched = [[0.0, 0.0], [12.0, 0.0], [0.0, 10.0]];
+        let forces = element.forces(stretched, STILL);
+        // the stretched corner is pulled back toward the others
+        assert!(forces[1][0] < 0.0);
+        let net = [0, 1].map(|axis| forces.iter().map(|f| f[axis]).sum::<f64>());
+        assert!(length(net) < 1e-9);
+    }
+
+    #[test]
+    fn degenerate_triangle_is_rejected() {
+        assert!(
+            FemElement::new([0, 1, 2], [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]], 4.0, 0.3).is_none()
+        );
+    }
+}
@@ -0,0 +1,266 @@
+// Force fields acting on everything inside a region of the world, on top of
+// gravity. Like gravity, the built-in fields all give an acceleration that is
+// scaled by each point's mass into a force, so light and heavy bodies respond
+// alike; anything else that implements `ForceField` can be added alongside
+// them.
+
+use std::sync::Arc;
+
+// how fast wind gusts change, in noise cells per frame
+const GUST_RATE: f64 = 0.01;
+
+pub trait ForceField {
+    /// Force on a point of `mass` at `position` moving at `velocity`, `time`
+    /// frames into the simulation.
+    fn force(&self, position: [f64; 2], velocity: [f64; 2], mass: f64, time: f64) -> [f64; 2];
+}
+
+/// Where a field acts.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Region {
+    Everywhere,
+    Circle { center: [f64; 2], radius: f64 },
+    // min x, min y, max x, max y
+    Rect([f64; 4]),
+}
+
+impl Region {
+    pub fn contains(&self, p: [f64; 2]) -> bool {
+        match *self {
+            Region::Everywhere => true,
+            Region::Circle { center, radius } => {
+                (p[0] - center[0]).hypot(p[1] - center[1]) < radius
+            }
+            Region::Rect([min_x, min_y, max_x, max_y]) => {
+                p[0] >= min_x && p[0] <= max_x && p[1] >= min_y && p[1] <= max_y
+            }
+        }
+    }
+}
+
+/// A force field and the region it's limited to.
+#[derive(Clone)]
+pub struct Field {
+    pub force: Arc<dyn ForceField + Send + Sync>,
+    pub region: Region,
+    pub enabled: bool,
+}
+
+impl Field {
+    /// A field acting everywhere.
+    pub fn new(force: impl ForceField + Send + Sync + 'static) -> Self {
+        Field {
+            force: Arc::new(force),
+            region: Region::Everywhere,
+            enabled: true,
+        }
+    }
+
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// The field's force on a point, or nothing outside its region.
+    pub fn force_on(
+        &self,
+        position: [f64; 2],
+        velocity: [f64; 2],
+        mass: f64,
+        time: f64,
+    ) -> [f64; 2] {
+        if !self.enabled || !self.region.contains(position) {
+            return [0.0, 0.0];
+        }
+        self.force.force(position, velocity, mass, time)
+    }
+}
+
+/// The same acceleration everywhere, like gravity; held to a region it makes
+/// an updraft.
+pub struct Uniform {
+    pub acceleration: [f64; 2],
+}
+
+impl ForceField for Uniform {
+    fn force(&self, _: [f64; 2], _: [f64; 2], mass: f64, _: f64) -> [f64; 2] {
+        [self.acceleration[0] * mass, self.acceleration[1] * mass]
+    }
+}
+
+/// Pulls toward `center`, or pushes away with a negative strength. The pull
+/// is `strength` up close and falls off with the square of the distance
+/// beyond `radius`.
+pub struct Attractor {
+    pub center: [f64; 2],
+    pub strength: f64,
+    pub radius: f64,
+}
+
+impl ForceField for Attractor {
+    fn force(&self, position: [f64; 2], _: [f64; 2], mass: f64, _: f64) -> [f64; 2] {
+        let d = [self.center[0] - position[0], self.center[1] - position[1]];
+        let distance = d[0].hypot(d[1]);
+        if distance == 0.0 {
+            return [0.0, 0.0];
+        }
+        let pull = self.strength * mass / (1.0 + (distance / self.radius).powi(2));
+        [d[0] / distance * pull, d[1] / distance * pull]
+    }
+}
+
+/// Swirls points around `center`, strongest at `radius` and still in the eye.
+/// Positive strength turns clockwise on screen.
+pub struct Vortex {
+    pub center: [f64; 2],
+    pub strength: f64,
+    pub radius: f64,
+}
+
+impl ForceField for Vortex {
+    fn force(&self, position: [f64; 2], _: [f64; 2], mass: f64, _: f64) -> [f64; 2] {
+        let d = [position[0] - self.center[0], position[1] - self.center[1]];
+        let distance = d[0].hypot(d[1]);
+        if distance == 0.0 {
+            return [0.0, 0.0];
+        }
+        let x = distance / self.radius;
+        let push = self.strength * mass * 2.0 * x / (1.0 + x * x);
+        [-d[1] / distance * push, d[0] / distance * push]
+    }
+}
+
+/// Drags points toward the wind's velocity, with gusts from smooth noise that
+/// drift over time. `scale` is the size of a gust.
+pub struct Wind {
+    pub velocity: [f64; 2],
+    // fraction of the difference from the wind's velocity made up per frame
+    pub drag: f64,
+    // largest gust speed, added to `velocity`
+    pub turbulence: f64,
+    pub scale: f64,
+}
+
+impl ForceField for Wind {
+    fn force(&self, position: [f64; 2], velocity: [f64; 2], mass: f64, time: f64) -> [f64; 2] {
+        let (x, y, t) = (
+            position[0] / self.scale,
+            position[1] / self.scale,
+            time * GUST_RATE,
+        );
+        // Separate, far apart samples of the noise for the two directions
+        let gust = [noise(x, y, t), noise(x + 57.3, y - 91.7, t + 13.1)];
+        [0, 1].map(|axis| {
+            let wind = self.velocity[axis] + gust[axis] * self.turbulence;
+            self.drag * mass * (wind - velocity[axis])
+        })
+    }
+}
+
+/// Slows points down, like moving through something thicker than air.
+pub struct Drag {
+    // fraction of the velocity removed per frame
+    pub coefficient: f64,
+}
+
+impl ForceField for Drag {
+    fn force(&self, _: [f64; 2], velocity: [f64; 2], mass: f64, _: f64) -> [f64; 2] {
+        [
+            -self.coefficient * mass * velocity[0],
+            -self.coefficient * mass * velocity[1],
+        ]
+    }
+}
+
+/// Pseudo-random value in -1..1 for a lattice corner.
+fn hash(x: i64, y: i64, t: i64) -> f64 {
+    let mut h = (x.wrapping_mul(73_856_093)
+        ^ y.wrapping_mul(19_349_663)
+        ^ t.wrapping_mul(83_492_791)) as u64;
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    (h % 2001) as f64 / 1000.0 - 1.0
+}
+
+/// Smooth value noise in -1..1 over space and time.
+fn noise(x: f64, y: f64, t: f64) -> f64 {
+    let (x0, y0, t0) = (x.floor(), y.floor(), t.floor());
+    let smooth = |f: f64| f * f * (3.0 - 2.0 * f);
+    let (fx, fy, ft) = (smooth(x - x0), smooth(y - y0), smooth(t - t0));
+    let (x0, y0, t0) = (x0 as i64, y0 as i64, t0 as i64);
+    let lerp = |a: f64, b: f64, f: f64| a + (b - a) * f;
+
+    let layer = |t: i64| {
+        let bottom = lerp(hash(x0, y0, t), hash(x0 + 1, y0, t), fx);
+        let top = lerp(hash(x0, y0 + 1, t), hash(x0 + 1, y0 + 1, t), fx);
+        lerp(bottom, top, fy)
+    };
+    lerp(layer(t0), layer(t0 + 1), ft)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_only_act_in_their_region() {
+        let field = Field::new(Uniform {
+            acceleration: [0.0, -1.0],
+        })
+        .with_region(Region::Circle {
+            center: [0.0, 0.0],
+            radius: 10.0,
+        });
+        assert_eq!(
+            field.force_on([5.0, 0.0], [0.0, 0.0], 2.0, 0.0),
+            [0.0, -2.0]
+        );
+        assert_eq!(
+            field.force_on([20.0, 0.0], [0.0, 0.0], 2.0, 0.0),
+            [0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn built_in_fields_accelerate_any_mass_alike() {
+        let fields: [Box<dyn ForceField>; 4] = [
+            Box::new(Attractor {
+                center: [0.0, 0.0],
+                strength: 0.3,
+                radius: 10.0,
+            }),
+            Box::new(Vortex {
+                center: [0.0, 0.0],
+                strength: 0.2,
+                radius: 10.0,
+            }),
+            Box::new(Wind {
+                velocity: [3.0, 0.0],
+                drag: 0.05,
+                turbulence: 3.0,
+                scale: 50.0,
+            }),
+            Box::new(Drag { coefficient: 0.1 }),
+        ];
+        let (position, velocity) = ([12.0, -7.0], [1.0, 2.0]);
+        for field in &fields {
+            let light = field.force(position, velocity, 1.0, 30.0);
+            let heavy = field.force(position, velocity, 4.0, 30.0);
+            assert_ne!(light, [0.0, 0.0]);
+            for axis in 0..2 {
+                assert!((heavy[axis] - light[axis] * 4.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_bounded_and_smooth() {
+        for i in 0..200 {
+            let x = i as f64 * 0.13;
+            let value = noise(x, x * 0.7, x * 0.3);
+            assert!((-1.0..=1.0).contains(&value));
+            assert!((noise(x + 1e-4, x * 0.7, x * 0.3) - value).abs() < 1e-2);
+        }
+    }
+}
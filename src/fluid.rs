@@ -0,0 +1,159 @@
+use crate::geometry;
+
+// mass of fluid per unit area; a jelly square at the default resolution has
+// about 0.0025 mass per unit area, so it floats a little over half under
+pub const DEFAULT_DENSITY: f64 = 0.004;
+// fraction of a submerged point's velocity the fluid takes per frame
+pub const DEFAULT_VISCOSITY: f64 = 0.05;
+
+/// A body of liquid filling a convex container up to its surface. Whatever
+/// is under the surface is buoyed up and slowed down. Bodies are clipped
+/// against the container to find how much of them is under, which only works
+/// for convex containers.
+#[derive(Clone, PartialEq)]
+pub struct Fluid {
+    // the container, counter-clockwise
+    pub outline: Vec<[f64; 2]>,
+    // mass of the fluid displaced per unit area, for buoyancy
+    pub density: f64,
+    pub viscosity: f64,
+    // height of the surface; the fluid fills the container below it
+    pub surface: f64,
+}
+
+impl Fluid {
+    /// Fills the container `outline` to the brim. Fails if the outline is
+    /// concave, rather than quietly filling out its notches.
+    pub fn new(outline: &[[f64; 2]]) -> Result<Self, String> {
+        let hull: Vec<[f64; 2]> = geometry::convex_hull(outline)
+            .into_iter()
+            .map(|i| outline[i])
+            .collect();
+        let area = geometry::signed_area(&hull).abs();
+        if area <= 0.0 {
+            return Err("a fluid container must enclose some area".to_string());
+        }
+        // Points along the hull's edges don't make it concave; notches do
+        if geometry::signed_area(outline).abs() < area * (1.0 - 1e-9) {
+            return Err("a fluid container must be convex".to_string());
+        }
+        Ok(Fluid::filled(hull))
+    }
+
+    /// A tank filling `[min_x, min_y, max_x, max_y]`.
+    pub fn new_rect([min_x, min_y, max_x, max_y]: [f64; 4]) -> Self {
+        Fluid::filled(vec![
+            [min_x, min_y],
+            [max_x, min_y],
+            [max_x, max_y],
+            [min_x, max_y],
+        ])
+    }
+
+    fn filled(outline: Vec<[f64; 2]>) -> Self {
+        let surface = outline.iter().fold(f64::MAX, |top, p| top.min(p[1]));
+        Fluid {
+            outline,
+            density: DEFAULT_DENSITY,
+            viscosity: DEFAULT_VISCOSITY,
+            surface,
+        }
+    }
+
+    pub fn with_density(mut self, density: f64) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_viscosity(mut self, viscosity: f64) -> Self {
+        self.viscosity = viscosity;
+        self
+    }
+
+    /// Fills the container to `level`, from 0 for empty to 1 for the brim.
+    pub fn with_level(mut self, level: f64) -> Self {
+        let [top, bottom] = self
+            .outline
+            .iter()
+            .fold([f64::MAX, f64::MIN], |[top, bottom], p| {
+                [top.min(p[1]), bottom.max(p[1])]
+            });
+        self.surface = bottom - (bottom - top) * level.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The space the fluid takes up: the container cut off at the surface.
+    pub fn volume(&self) -> Vec<[f64; 2]> {
+        let [min_x, max_x] = self
+            .outline
+            .iter()
+            .fold([f64::MAX, f64::MIN], |[min, max], p| {
+                [min.min(p[0]), max.max(p[0])]
+            });
+        let bottom = self.outline.iter().fold(f64::MIN, |b, p| b.max(p[1]));
+        let below_surface = [
+            [min_x, self.surface],
+            [max_x, self.surface],
+            [max_x, bottom],
+            [min_x, bottom],
+        ];
+        geometry::clip_polygon(&self.outline, &below_surface)
+    }
+
+    pub fn contains(&self, p: [f64; 2]) -> bool {
+        p[1] >= self.surface && geometry::contains(&self.outline, p)
+    }
+
+    /// Area and centroid of the part of `polygon` under the fluid, if any of
+    /// it is.
+    pub fn submerged(&self, polygon: &[[f64; 2]]) -> Option<(f64, [f64; 2])> {
+        let wet = geometry::clip_polygon(polygon, &self.volume());
+        let area = geometry::signed_area(&wet).abs();
+        (area > 0.0).then(|| (area, geometry::centroid(&wet)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers_must_be_convex() {
+        // A point partway along an edge leaves it convex
+        let square = [
+            [0.0, 0.0],
+            [50.0, 0.0],
+            [100.0, 0.0],
+            [100.0, 100.0],
+            [0.0, 100.0],
+        ];
+        let fluid = Fluid::new(&square).unwrap();
+        assert_eq!(fluid.outline.len(), 4);
+        assert_eq!(fluid.surface, 0.0);
+
+        let notched = [
+            [0.0, 0.0],
+            [100.0, 0.0],
+            [100.0, 100.0],
+            [50.0, 50.0],
+            [0.0, 100.0],
+        ];
+        assert!(Fluid::new(&notched).is_err());
+        assert!(Fluid::new(&[[0.0, 0.0], [100.0, 0.0], [50.0, 0.0]]).is_err());
+    }
+
+    #[test]
+    fn submerged_part_is_clipped_at_the_surface() {
+        let fluid = Fluid::new_rect([0.0, 0.0, 100.0, 100.0]).with_level(0.5);
+        assert_eq!(fluid.surface, 50.0);
+        let square = [[10.0, 40.0], [30.0, 40.0], [30.0, 60.0], [10.0, 60.0]];
+        let (area, center) = fluid.submerged(&square).unwrap();
+        assert!((area - 200.0).abs() < 1e-9);
+        assert!((center[0] - 20.0).abs() < 1e-9 && (center[1] - 55.0).abs() < 1e-9);
+        assert!(
+            fluid
+                .submerged(&square.map(|p| [p[0], p[1] - 30.0]))
+                .is_none()
+        );
+    }
+}
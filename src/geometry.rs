@@ -0,0 +1,324 @@
+// Small polygon helpers shared by the renderer and the simulation.
+// Polygons are slices of vertices in order, closed implicitly.
+
+pub fn signed_area(polygon: &[[f64; 2]]) -> f64 {
+    let n = polygon.len();
+    let mut area = 0.0;
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        area += a[0] * b[1] - b[0] * a[1];
+    }
+    area * 0.5
+}
+
+fn cross(o: [f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+}
+
+fn in_triangle(p: [f64; 2], a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+/// Area-weighted centre of the polygon, or its first vertex if it has no area.
+pub fn centroid(polygon: &[[f64; 2]]) -> [f64; 2] {
+    let area = signed_area(polygon);
+    if area == 0.0 {
+        return polygon.first().copied().unwrap_or([0.0, 0.0]);
+    }
+    let n = polygon.len();
+    let mut c = [0.0, 0.0];
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        let w = a[0] * b[1] - b[0] * a[1];
+        c[0] += (a[0] + b[0]) * w;
+        c[1] += (a[1] + b[1]) * w;
+    }
+    [c[0] / (6.0 * area), c[1] / (6.0 * area)]
+}
+
+/// The part of `subject` inside `clip` (Sutherland-Hodgman). `clip` must be
+/// convex, in either winding; `subject` can be any simple polygon, though a
+/// concave one cut in two comes back joined by zero-width seams.
+pub fn clip_polygon(subject: &[[f64; 2]], clip: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let side = signed_area(clip).signum();
+    let mut output = subject.to_vec();
+    for i in 0..clip.len() {
+        if output.is_empty() {
+            break;
+        }
+        let (a, b) = (clip[i], clip[(i + 1) % clip.len()]);
+        let inside = |p: [f64; 2]| cross(a, b, p) * side >= 0.0;
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let (p, q) = (input[j], input[(j + 1) % input.len()]);
+            if inside(p) {
+                output.push(p);
+            }
+            if inside(p) != inside(q) {
+                // where p-q crosses the clip edge's line
+                let (dp, dq) = (cross(a, b, p), cross(a, b, q));
+                let t = dp / (dp - dq);
+                output.push([p[0] + (q[0] - p[0]) * t, p[1] + (q[1] - p[1]) * t]);
+            }
+        }
+    }
+    output
+}
+
+/// Even-odd test for whether `p` lies inside the polygon.
+pub fn contains(polygon: &[[f64; 2]], p: [f64; 2]) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// The point on segment `a`-`b` closest to `p`.
+pub fn closest_on_segment(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 {
+        0.0
+    } else {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / len_sq).clamp(0.0, 1.0)
+    };
+    [a[0] + dx * t, a[1] + dy * t]
+}
+
+pub fn distance_to_segment(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    let q = closest_on_segment(p, a, b);
+    (q[0] - p[0]).hypot(q[1] - p[1])
+}
+
+/// Distance from `p` to the nearest edge of the polygon.
+pub fn distance_to_outline(polygon: &[[f64; 2]], p: [f64; 2]) -> f64 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| distance_to_segment(p, polygon[i], polygon[(i + 1) % n]))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Splits a simple polygon into triangles by ear clipping, returning vertex indices.
+/// Self-intersecting input (e.g. a badly crushed body) still yields a fan for
+/// whatever is left once no proper ear can be found.
+pub fn triangulate(polygon: &[[f64; 2]]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    if signed_area(polygon) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(polygon.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (ia, ib, ic) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (a, b, c) = (polygon[ia], polygon[ib], polygon[ic]);
+            cross(a, b, c) > 0.0
+                && remaining
+                    .iter()
+                    .all(|&j| j == ia || j == ib || j == ic || !in_triangle(polygon[j], a, b, c))
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    for i in 1..remaining.len().saturating_sub(1) {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+/// Chaikin corner cutting on a closed polygon; each iteration doubles the vertex count.
+pub fn chaikin(polygon: &[[f64; 2]], iterations: u32) -> Vec<[f64; 2]> {
+    let mut current = polygon.to_vec();
+    for _ in 0..iterations {
+        let n = current.len();
+        let mut next = Vec::with_capacity(n * 2);
+        for i in 0..n {
+            let a = current[i];
+            let b = current[(i + 1) % n];
+            next.push([0.75 * a[0] + 0.25 * b[0], 0.75 * a[1] + 0.25 * b[1]]);
+            next.push([0.25 * a[0] + 0.75 * b[0], 0.25 * a[1] + 0.75 * b[1]]);
+        }
+        current = next;
+    }
+    current
+}
+
+/// Indices of the convex hull of `points`, counter-clockwise (monotone chain).
+pub fn convex_hull(points: &[[f64; 2]]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| {
+        points[a][0]
+            .total_cmp(&points[b][0])
+            .then(points[a][1].total_cmp(&points[b][1]))
+    });
+    if order.len() < 3 {
+        return order;
+    }
+
+    let mut hull: Vec<usize> = Vec::with_capacity(order.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &i in &order {
+            while hull.len() >= start + 2
+                && cross(
+                    points[hull[hull.len() - 2]],
+                    points[hull[hull.len() - 1]],
+                    points[i],
+                ) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(i);
+        }
+        hull.pop();
+        if pass == 0 {
+            order.reverse();
+        }
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [[f64; 2]; 4] = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+
+    #[test]
+    fn centroid_is_area_weighted() {
+        assert_eq!(centroid(&SQUARE), [5.0, 5.0]);
+        // An L is pulled toward its long arm, not the average of its corners
+        let l_shape = [
+            [0.0, 0.0],
+            [30.0, 0.0],
+            [30.0, 10.0],
+            [10.0, 10.0],
+            [10.0, 30.0],
+            [0.0, 30.0],
+        ];
+        let c = centroid(&l_shape);
+        assert!((c[0] - 11.0).abs() < 1e-9 && (c[1] - 11.0).abs() < 1e-9);
+        assert_eq!(centroid(&[[1.0, 2.0], [3.0, 4.0]]), [1.0, 2.0]);
+    }
+
+    #[test]
+    fn signed_area_follows_winding() {
+        assert_eq!(signed_area(&SQUARE), 100.0);
+        let mut reversed = SQUARE;
+        reversed.reverse();
+        assert_eq!(signed_area(&reversed), -100.0);
+    }
+
+    #[test]
+    fn triangulation_covers_a_concave_polygon() {
+        let arrow = [[0.0, 0.0], [10.0, 5.0], [0.0, 10.0], [3.0, 5.0]];
+        for polygon in [arrow.to_vec(), arrow.iter().rev().copied().collect()] {
+            let triangles = triangulate(&polygon);
+            assert_eq!(triangles.len(), 2);
+            let area: f64 = triangles
+                .iter()
+                .map(|t| signed_area(&t.map(|i| polygon[i])).abs())
+                .sum();
+            assert!((area - signed_area(&polygon).abs()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn hull_skips_inner_and_collinear_points() {
+        let points = [
+            [5.0, 5.0],
+            [0.0, 0.0],
+            [5.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [0.0, 10.0],
+            [2.0, 7.0],
+        ];
+        let hull = convex_hull(&points);
+        assert_eq!(hull.len(), 4);
+        let outline: Vec<[f64; 2]> = hull.iter().map(|&i| points[i]).collect();
+        assert_eq!(signed_area(&outline), 100.0);
+    }
+
+    #[test]
+    fn chaikin_rounds_corners_inside_the_polygon() {
+        let smooth = chaikin(&SQUARE, 2);
+        assert_eq!(smooth.len(), 16);
+        assert!(
+            smooth
+                .iter()
+                .all(|&p| contains(&SQUARE, p) || distance_to_outline(&SQUARE, p) < 1e-9)
+        );
+        assert!(signed_area(&smooth) < signed_area(&SQUARE));
+    }
+
+    #[test]
+    fn containment_and_distance_handle_concave_outlines() {
+        let u_shape = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 20.0],
+            [20.0, 20.0],
+            [20.0, 0.0],
+            [30.0, 0.0],
+            [30.0, 30.0],
+            [0.0, 30.0],
+        ];
+        assert!(contains(&u_shape, [5.0, 5.0]));
+        assert!(!contains(&u_shape, [15.0, 5.0]));
+        assert!(contains(&u_shape, [15.0, 25.0]));
+        assert!(!contains(&u_shape, [-1.0, 5.0]));
+        assert_eq!(distance_to_outline(&u_shape, [15.0, 5.0]), 5.0);
+        assert_eq!(distance_to_outline(&u_shape, [15.0, 27.0]), 3.0);
+    }
+
+    #[test]
+    fn closest_point_is_clamped_to_the_segment() {
+        let (a, b) = ([0.0, 0.0], [10.0, 0.0]);
+        assert_eq!(closest_on_segment([4.0, 3.0], a, b), [4.0, 0.0]);
+        assert_eq!(closest_on_segment([-5.0, 3.0], a, b), a);
+        assert_eq!(closest_on_segment([15.0, -3.0], a, b), b);
+        assert_eq!(closest_on_segment([1.0, 1.0], a, a), a);
+        assert_eq!(distance_to_segment([13.0, 4.0], a, b), 5.0);
+    }
+
+    #[test]
+    fn clipping_keeps_the_overlap_in_either_winding() {
+        let shifted = SQUARE.map(|p| [p[0] + 5.0, p[1] + 5.0]);
+        let mut reversed = SQUARE;
+        reversed.reverse();
+        for clip in [SQUARE, reversed] {
+            let overlap = clip_polygon(&shifted, &clip);
+            assert!((signed_area(&overlap).abs() - 25.0).abs() < 1e-9);
+            assert_eq!(centroid(&overlap), [7.5, 7.5]);
+        }
+        let apart = SQUARE.map(|p| [p[0] + 20.0, p[1]]);
+        assert!(signed_area(&clip_polygon(&apart, &SQUARE)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,211 @@
+// Backward Euler for springs, after Baraff & Witkin's "Large Steps in Cloth
+// Simulation". Instead of pushing points with the spring forces at the start of
+// the step, each step solves for the velocities whose forces at the end of the
+// step are consistent with them. Stiff springs then settle instead of
+// overshooting, however large the step.
+
+type Matrix = [[f64; 2]; 2];
+
+// conjugate gradient stops after this many iterations, or once the residual is
+// this small relative to the right-hand side
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-8;
+
+/// Stiffness of one spring, as the 2x2 block that turns a relative
+/// displacement of its ends into a restoring force.
+#[derive(Clone, Copy)]
+pub struct SpringJacobian {
+    pub point1: usize,
+    pub point2: usize,
+    // moving `point1` by `d` relative to `point2` adds `-block * d` to its force
+    block: Matrix,
+}
+
+impl SpringJacobian {
+    /// Linearises a spring with stiffness `stiffness` and rest length
+    /// `rest_length` around its current shape.
+    pub fn new(
+        point1: usize,
+        point2: usize,
+        positions: [[f64; 2]; 2],
+        stiffness: f64,
+        rest_length: f64,
+    ) -> Option<Self> {
+        let dx = positions[1][0] - positions[0][0];
+        let dy = positions[1][1] - positions[0][1];
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return None;
+        }
+        let n = [dx / length, dy / length];
+
+        // Along the spring it's the plain stiffness; across it, the tension
+        // turns the spring with the points. A compressed spring would buckle
+        // sideways, which makes the system indefinite, so that part is dropped.
+        let tension = (1.0 - rest_length / length).max(0.0);
+        let mut block = [[0.0; 2]; 2];
+        for i in 0..2 {
+            for j in 0..2 {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                block[i][j] = stiffness * (n[i] * n[j] + tension * (identity - n[i] * n[j]));
+            }
+        }
+        Some(SpringJacobian {
+            point1,
+            point2,
+            block,
+        })
+    }
+}
+
+fn dot(a: &[[f64; 2]], b: &[[f64; 2]]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| a[0] * b[0] + a[1] * b[1])
+        .sum()
+}
+
+/// Stiffness matrix times `x`, with the sign of a restoring force flipped.
+fn stiffness_times(jacobians: &[SpringJacobian], x: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let mut out = vec![[0.0; 2]; x.len()];
+    for jacobian in jacobians {
+        let (i, j) = (jacobian.point1, jacobian.point2);
+        let d = [x[i][0] - x[j][0], x[i][1] - x[j][1]];
+        let b = jacobian.block;
+        let k = [
+            b[0][0] * d[0] + b[0][1] * d[1],
+            b[1][0] * d[0] + b[1][1] * d[1],
+        ];
+        out[i][0] += k[0];
+        out[i][1] += k[1];
+        out[j][0] -= k[0];
+        out[j][1] -= k[1];
+    }
+    out
+}
+
+/// Velocity change over a step of `dt`, given the forces at the start of the
+/// step. Solves `(M + dt² K) Δv = dt (f - dt K v)` by conjugate gradient; the
+/// matrix is symmetric positive definite, so it always converges.
+pub fn velocity_change(
+    jacobians: &[SpringJacobian],
+    masses: &[f64],
+    velocities: &[[f64; 2]],
+    forces: &[[f64; 2]],
+    dt: f64,
+) -> Vec<[f64; 2]> {
+    let system = |x: &[[f64; 2]]| -> Vec<[f64; 2]> {
+        let k = stiffness_times(jacobians, x);
+        x.iter()
+            .zip(masses)
+            .zip(k)
+            .map(|((x, m), k)| [m * x[0] + dt * dt * k[0], m * x[1] + dt * dt * k[1]])
+            .collect()
+    };
+
+    let kv = stiffness_times(jacobians, velocities);
+    let rhs: Vec<[f64; 2]> = forces
+        .iter()
+        .zip(kv)
+        .map(|(f, kv)| [dt * (f[0] - dt * kv[0]), dt * (f[1] - dt * kv[1])])
+        .collect();
+
+    // Start from the explicit step, which is already close for soft springs
+    let mut x: Vec<[f64; 2]> = rhs
+        .iter()
+        .zip(masses)
+        .map(|(r, m)| [r[0] / m, r[1] / m])
+        .collect();
+    let ax = system(&x);
+    let mut r: Vec<[f64; 2]> = rhs
+        .iter()
+        .zip(ax)
+        .map(|(b, ax)| [b[0] - ax[0], b[1] - ax[1]])
+        .collect();
+    let mut p = r.clone();
+    let mut r_sq = dot(&r, &r);
+    let threshold = TOLERANCE * TOLERANCE * dot(&rhs, &rhs);
+
+    for _ in 0..MAX_ITERATIONS {
+        if r_sq <= threshold {
+            break;
+        }
+        let ap = system(&p);
+        let p_ap = dot(&p, &ap);
+        if p_ap <= 0.0 {
+            break;
+        }
+        let alpha = r_sq / p_ap;
+        for ((x, r), (p, ap)) in x.iter_mut().zip(&mut r).zip(p.iter().zip(&ap)) {
+            for axis in 0..2 {
+                x[axis] += alpha * p[axis];
+                r[axis] -= alpha * ap[axis];
+            }
+        }
+        let next_r_sq = dot(&r, &r);
+        let beta = next_r_sq / r_sq;
+        r_sq = next_r_sq;
+        for (p, r) in p.iter_mut().zip(&r) {
+            p[0] = r[0] + beta * p[0];
+            p[1] = r[1] + beta * p[1];
+        }
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain of springs stretched to 1.5 times their rest length.
+    fn chain(stiffness: f64) -> (Vec<SpringJacobian>, Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let positions: Vec<[f64; 2]> = (0..5).map(|i| [i as f64 * 15.0, i as f64 * 2.0]).collect();
+        let mut jacobians = Vec::new();
+        let mut forces = vec![[0.0; 2]; positions.len()];
+        for i in 0..positions.len() - 1 {
+            let (a, b) = (positions[i], positions[i + 1]);
+            let d = [b[0] - a[0], b[1] - a[1]];
+            let length = d[0].hypot(d[1]);
+            let rest = length / 1.5;
+            jacobians.push(SpringJacobian::new(i, i + 1, [a, b], stiffness, rest).unwrap());
+            let pull = stiffness * (length - rest) / length;
+            for axis in 0..2 {
+                forces[i][axis] += pull * d[axis];
+                forces[i + 1][axis] -= pull * d[axis];
+            }
+        }
+        (jacobians, positions, forces)
+    }
+
+    #[test]
+    fn conjugate_gradient_solves_the_system() {
+        let (jacobians, positions, forces) = chain(0.8);
+        let masses = [1.0, 2.0, 1.0, 3.0, 1.0];
+        let velocities: Vec<[f64; 2]> = positions.iter().map(|p| [p[1] * 0.1, -0.2]).collect();
+        let dt = 1.0;
+        let dv = velocity_change(&jacobians, &masses, &velocities, &forces, dt);
+
+        let k_dv = stiffness_times(&jacobians, &dv);
+        let k_v = stiffness_times(&jacobians, &velocities);
+        for i in 0..dv.len() {
+            for axis in 0..2 {
+                let lhs = masses[i] * dv[i][axis] + dt * dt * k_dv[i][axis];
+                let rhs = dt * (forces[i][axis] - dt * k_v[i][axis]);
+                assert!((lhs - rhs).abs() < 1e-6, "point {} axis {}", i, axis);
+            }
+        }
+    }
+
+    #[test]
+    fn stiff_springs_do_not_overshoot() {
+        let (jacobians, positions, forces) = chain(1000.0);
+        let masses = [1.0; 5];
+        let rest = [[0.0; 2]; 5];
+        let dv = velocity_change(&jacobians, &masses, &rest, &forces, 1.0);
+        // The ends come in by no more than the stretch, where an explicit
+        // step would throw them hundreds of times further
+        let stretch = (positions[1][0] - positions[0][0]) / 3.0;
+        assert!(dv[0][0] > 0.0 && dv[0][0] < 2.0 * stretch);
+        assert!(dv[4][0] < 0.0 && dv[4][0] > -2.0 * stretch);
+    }
+}
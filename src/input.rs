@@ -1,45 +1,96 @@
-use crate::simulation::SoftBodyCollection;
+use crate::camera::Camera;
+use crate::command::SimCommand;
+use crate::simulation::{self, SoftBodyCollection};
+
+pub const PICK_RADIUS: f64 = 10.0; // in screen pixels
 
 pub struct InputHandler {
     pub mouse_pos: [f64; 2],
+    pub world_pos: [f64; 2],
     pub mouse_down: bool,
+    pub panning: bool,
     pub held_point_index: Option<usize>,
     pub softbody_index: Option<usize>,
+    // world position where a placement drag started
+    pub place_start: Option<[f64; 2]>,
+    // (softbody, point) marked as the first end of a joint
+    pub joint_start: Option<(usize, usize)>,
 }
 
 impl InputHandler {
     pub fn new() -> Self {
         InputHandler {
             mouse_pos: [0.0, 0.0],
+            world_pos: [0.0, 0.0],
             mouse_down: false,
+            panning: false,
             held_point_index: None,
             softbody_index: None,
+            place_start: None,
+            joint_start: None,
         }
     }
 
-    pub fn handle_mouse_move(&mut self, pos: [f64; 2]) {
+    /// Moves the cursor, panning the camera if the middle button is held.
+    pub fn handle_mouse_move(&mut self, pos: [f64; 2], camera: &mut Camera) {
+        if self.panning {
+            camera.pan([pos[0] - self.mouse_pos[0], pos[1] - self.mouse_pos[1]]);
+        }
         self.mouse_pos = pos;
+        self.update_world_pos(camera);
     }
 
-    pub fn handle_mouse_down(&mut self, softbodies: SoftBodyCollection) {
-        self.mouse_down = true;
-        let mut found = false;
-        for (sbi, sb) in softbodies.softbodies.iter().enumerate() {
-            if let Some(pi) = sb.points.iter().position(|p| {
-                let dx = p.position[0] - self.mouse_pos[0];
-                let dy = p.position[1] - self.mouse_pos[1];
-                (dx * dx + dy * dy).sqrt() < 10.0
-            }) {
-                self.softbody_index = Some(sbi);
-                self.held_point_index = Some(pi);
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            self.softbody_index = None;
+    /// Re-projects the cursor after the camera moved underneath it.
+    pub fn update_world_pos(&mut self, camera: &Camera) {
+        self.world_pos = camera.screen_to_world(self.mouse_pos);
+    }
+
+    /// Returns the (softbody, point) indices of the first point under the cursor.
+    pub fn pick(&self, softbodies: &SoftBodyCollection, camera: &Camera) -> Option<(usize, usize)> {
+        let radius = PICK_RADIUS / camera.zoom;
+        softbodies
+            .softbodies
+            .iter()
+            .enumerate()
+            .find_map(|(sbi, sb)| {
+                sb.points
+                    .iter()
+                    .position(|p| {
+                        let dx = p.position[0] - self.world_pos[0];
+                        let dy = p.position[1] - self.world_pos[1];
+                        (dx * dx + dy * dy).sqrt() < radius
+                    })
+                    .map(|pi| (sbi, pi))
+            })
+    }
+
+    /// Renumbers the held point and marked joint end after softbody `index`
+    /// is removed, letting go of them if they were on it.
+    pub fn softbody_removed(&mut self, index: usize) {
+        self.softbody_index = self
+            .softbody_index
+            .and_then(|body| simulation::renumber(body, index));
+        if self.softbody_index.is_none() {
             self.held_point_index = None;
         }
+        self.joint_start = self
+            .joint_start
+            .and_then(|(body, point)| Some((simulation::renumber(body, index)?, point)));
+    }
+
+    /// Index of the topmost rigid body under the cursor.
+    pub fn pick_rigid(&self, softbodies: &SoftBodyCollection) -> Option<usize> {
+        softbodies
+            .rigid_bodies
+            .iter()
+            .rposition(|body| body.contains(self.world_pos))
+    }
+
+    pub fn handle_mouse_down(&mut self, softbodies: &SoftBodyCollection, camera: &Camera) {
+        self.mouse_down = true;
+        let picked = self.pick(softbodies, camera);
+        self.softbody_index = picked.map(|(sbi, _)| sbi);
+        self.held_point_index = picked.map(|(_, pi)| pi);
     }
 
     pub fn handle_mouse_up(&mut self) {
@@ -47,4 +98,40 @@ impl InputHandler {
         self.held_point_index = None;
         self.softbody_index = None;
     }
+
+    pub fn drag_command(&self) -> Option<SimCommand> {
+        match (self.softbody_index, self.held_point_index) {
+            (Some(body), Some(point)) if self.mouse_down => Some(SimCommand::Drag {
+                body,
+                point,
+                target: self.world_pos,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removal_renumbers_or_lets_go() {
+        let mut input = InputHandler::new();
+        input.softbody_index = Some(3);
+        input.held_point_index = Some(7);
+        input.joint_start = Some((1, 2));
+        input.softbody_removed(2);
+        assert_eq!(input.softbody_index, Some(2));
+        assert_eq!(input.held_point_index, Some(7));
+        assert_eq!(input.joint_start, Some((1, 2)));
+
+        input.softbody_removed(1);
+        assert_eq!(input.softbody_index, Some(1));
+        assert_eq!(input.joint_start, None);
+
+        input.softbody_removed(1);
+        assert_eq!(input.softbody_index, None);
+        assert_eq!(input.held_point_index, None);
+    }
 }
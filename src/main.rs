@@ -1,56 +1,67 @@
-use piston::{mouse, Button};
-use piston_window::{RenderEvent, TextureSettings};
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::thread;
+use piston::{Button, Key, mouse};
+use piston_window::{RenderEvent, ResizeEvent, TextureSettings, Window};
+
+use camera::Camera;
 use std::time::Instant;
 
 mod app;
+mod broadphase;
+mod camera;
+mod collider;
+mod colormap;
+mod command;
+mod config;
+mod controls;
+mod fem;
+mod field;
+mod fluid;
+mod geometry;
+mod implicit;
 mod input;
+mod mesh;
+mod palette;
 mod render;
+mod rigid;
+mod sim_thread;
 mod simulation;
+mod snapshot;
+mod svg;
 mod ui;
 
-use simulation::{SoftBody, SoftBodyCollection};
+use command::SimCommand;
+use simulation::{Joint, SoftBody, SoftBodyCollection};
 
 fn main() {
-    let mut input_handler = input::InputHandler::new();
+    let config = match config::Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let window_size = [800.0, 600.0];
+    let mut input_handler = input::InputHandler::new();
 
     let mut frame_count = 0;
     let mut last_fps_check = Instant::now();
 
-    // Two-way communication
-    let (to_sim_tx, to_sim_rx): (Sender<SoftBodyCollection>, Receiver<SoftBodyCollection>) =
-        mpsc::channel();
-    let (from_sim_tx, from_sim_rx): (Sender<SoftBodyCollection>, Receiver<SoftBodyCollection>) =
-        mpsc::channel();
-    let (to_sim_pause_tx, to_sim_pause_rx): (Sender<bool>, Receiver<bool>) = mpsc::channel();
-    let (from_sim_pause_tx, from_sim_pause_rx): (Sender<bool>, Receiver<bool>) = mpsc::channel();
-
-    let mut pause = false;
-
     println!("Starting Soft Body Simulation...");
 
     let mut window: piston_window::PistonWindow =
-        piston_window::WindowSettings::new("Soft Body Simulation", window_size)
+        piston_window::WindowSettings::new("Soft Body Simulation", config.window_size)
             .exit_on_esc(true)
+            .vsync(config.vsync)
+            .samples(config.samples)
+            .fullscreen(config.fullscreen)
             .build()
             .unwrap();
 
-    let spawn_button = ui::Button::new(
-        [50.0, 50.0],
-        [100.0, 50.0],
-        [0.2, 0.6, 0.8, 1.0],
-        "Spawn".to_string(),
-    );
+    // Fullscreen windows won't match the requested size
+    let size = window.size();
+    let mut window_size = [size.width, size.height];
 
-    let pause_button = ui::Button::new(
-        [450.0, 50.0],
-        [100.0, 50.0],
-        [0.5, 0.5, 0.2, 1.0],
-        "Pause".to_string(),
-    );
+    let mut ui = ui::Ui::new();
+    let mut controls = controls::Controls::new();
 
     let mut glyphs = piston_window::Glyphs::new(
         "src/resources/FiraSans-Regular.ttf",
@@ -59,104 +70,272 @@ fn main() {
     )
     .unwrap();
 
-    // Spawn simulation thread
-    thread::spawn(move || {
-        let mut softbodycollection = SoftBodyCollection::new();
-        softbodycollection.add(SoftBody::new_square([100.0, 300.0], 200.0, 3));
-        softbodycollection.add(SoftBody::new_square([0.0, 100.0], 150.0, 6));
-        softbodycollection.add(SoftBody::new_square([0.0, 0.0], 100.0, 5));
-        loop {
-            if let Ok(p) = to_sim_pause_rx.try_recv() {
-                pause = p;
-            }
-            // Try to receive an updated softbody from UI
-            while let Ok(updated) = to_sim_rx.try_recv() {
-                softbodycollection = updated;
+    // The simulation thread owns the softbodies from here on; the UI only reads
+    // published snapshots and sends commands back
+    let softbodycollection = match &config.scene {
+        Some(path) => match svg::load(path, &config.svg) {
+            Ok((scene, skipped)) => {
+                for message in skipped {
+                    eprintln!("warning: {}", message);
+                }
+                scene
             }
-
-            if !pause {
-                softbodycollection.update(&window_size);
-
-                from_sim_tx.send(softbodycollection.clone()).unwrap();
-                thread::sleep(std::time::Duration::from_millis(16)); // ~60 FPS
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
             }
-
-            from_sim_pause_tx.send(pause).unwrap();
+        },
+        None => {
+            let mut softbodycollection = SoftBodyCollection::new();
+            softbodycollection.add(
+                SoftBody::new_square([100.0, 300.0], 200.0, 3).with_color([0.9, 0.4, 0.3, 1.0]),
+            );
+            softbodycollection
+                .add(SoftBody::new_square([0.0, 100.0], 150.0, 6).with_color([0.3, 0.8, 0.4, 1.0]));
+            softbodycollection
+                .add(SoftBody::new_square([0.0, 0.0], 100.0, 5).with_color([0.9, 0.8, 0.3, 1.0]));
+            softbodycollection
         }
-    });
+    };
+    let world_size = config.world_size;
+    let mut app = app::App::new(world_size, softbodycollection);
+    let mut render_settings = render::RenderSettings::new();
+    let mut debug_settings = render::DebugSettings::new();
+    let mut camera = Camera::new(window_size, [world_size[0] / 2.0, world_size[1] / 2.0]);
 
     while let Some(event) = window.next() {
         // Track mouse position
         if let Some(pos) = mouse::MouseCursorEvent::mouse_cursor_args(&event) {
-            input_handler.handle_mouse_move(pos);
+            input_handler.handle_mouse_move(pos, &mut camera);
+            ui.handle_mouse_move(pos);
+            if !ui.wants_mouse() {
+                let direction = [pos[0] - window_size[0] / 2.0, pos[1] - window_size[1] / 2.0];
+                controls.follow_mouse(direction, &app);
+            }
         }
 
-        // Mouse press: find the closest point in any softbody
-        if let Some(Button::Mouse(button)) = piston::PressEvent::press_args(&event) {
-            if button == piston::MouseButton::Left {
-                if let Some(softbodies) = from_sim_rx.try_iter().last() {
-                    input_handler.handle_mouse_down(softbodies.clone());
-
-                    //spawn a softbody if the spawn button is clicked
-                    spawn_button.handle_click_spawn(
-                        input_handler.mouse_pos,
-                        softbodies.clone(),
-                        &to_sim_tx,
-                    );
+        match piston::PressEvent::press_args(&event) {
+            // Clicks on a panel belong to the UI; the rest place a new body or
+            // grab the closest point
+            Some(Button::Mouse(piston::MouseButton::Left)) => {
+                if ui.wants_mouse() {
+                    ui.handle_mouse_down();
+                } else if controls.palette.placing {
+                    input_handler.place_start = Some(input_handler.world_pos);
+                } else if controls.palette.drawing {
+                    let close_radius = input::PICK_RADIUS / camera.zoom;
+                    match controls
+                        .palette
+                        .add_vertex(input_handler.world_pos, close_radius)
+                    {
+                        Ok(Some(command)) => app.send(command),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("error: {}", e),
+                    }
+                } else {
+                    input_handler.handle_mouse_down(app.softbodies(), &camera);
+                    if let Some(drag) = input_handler.drag_command() {
+                        app.send(drag);
+                    }
                 }
-                pause = pause_button.handle_click_pause(input_handler.mouse_pos, pause);
-                to_sim_pause_tx.send(pause).unwrap();
             }
+            // Right click: delete the softbody under the cursor, or failing
+            // that the rigid body
+            Some(Button::Mouse(piston::MouseButton::Right)) => {
+                if let Some((body, _)) = input_handler.pick(app.softbodies(), &camera) {
+                    app.send(SimCommand::Remove(body));
+                    // The sim renumbers the bodies after it; keep up here too
+                    input_handler.softbody_removed(body);
+                    camera.softbody_removed(body);
+                } else if let Some(body) = input_handler.pick_rigid(app.softbodies()) {
+                    app.send(SimCommand::RemoveRigid(body));
+                }
+            }
+            Some(Button::Mouse(piston::MouseButton::Middle)) => input_handler.panning = true,
+            // F: follow the softbody under the cursor, or stop following
+            Some(Button::Keyboard(Key::F)) => {
+                camera.follow = input_handler
+                    .pick(app.softbodies(), &camera)
+                    .map(|(body, _)| body);
+            }
+            // J: mark the point under the cursor, then join it to the next one
+            // marked with the palette's joint; J over empty space cancels
+            Some(Button::Keyboard(Key::J)) => {
+                let picked = input_handler.pick(app.softbodies(), &camera);
+                match (input_handler.joint_start.take(), picked) {
+                    (Some(start), Some(end)) if start != end => {
+                        app.send(SimCommand::AddJoint(Joint::new(
+                            controls.palette.joint,
+                            &app.softbodies().softbodies,
+                            start,
+                            end,
+                        )))
+                    }
+                    (None, picked) => input_handler.joint_start = picked,
+                    _ => {}
+                }
+            }
+            // T: fill a tank of water around the cursor
+            Some(Button::Keyboard(Key::T)) => {
+                app.send(controls.palette.tank_at(input_handler.world_pos))
+            }
+            // Q: put the palette's force field under the cursor
+            Some(Button::Keyboard(Key::Q)) => {
+                app.send(controls.palette.field_at(input_handler.world_pos))
+            }
+            // Left / Right: turn gravity, when the arrow keys are steering it
+            Some(Button::Keyboard(Key::Left)) => controls.turn_gravity(-1.0, &app),
+            Some(Button::Keyboard(Key::Right)) => controls.turn_gravity(1.0, &app),
+            Some(Button::Keyboard(Key::Home)) => camera.reset(),
+            Some(Button::Keyboard(Key::K)) => render_settings.fill = !render_settings.fill,
+            Some(Button::Keyboard(Key::O)) => render_settings.outline = !render_settings.outline,
+            Some(Button::Keyboard(Key::C)) => {
+                render_settings.smoothing = if render_settings.smoothing == 0 { 2 } else { 0 }
+            }
+            Some(Button::Keyboard(Key::W)) => {
+                render_settings.wireframe = !render_settings.wireframe
+            }
+            Some(Button::Keyboard(Key::H)) => render_settings.cycle_heatmap(),
+            Some(Button::Keyboard(Key::LeftBracket)) => render_settings.heatmap_range /= 2.0,
+            Some(Button::Keyboard(Key::RightBracket)) => render_settings.heatmap_range *= 2.0,
+            Some(Button::Keyboard(Key::F1)) => {
+                debug_settings.velocities = !debug_settings.velocities
+            }
+            Some(Button::Keyboard(Key::F2)) => debug_settings.forces = !debug_settings.forces,
+            Some(Button::Keyboard(Key::F3)) => debug_settings.contacts = !debug_settings.contacts,
+            Some(Button::Keyboard(Key::F4)) => debug_settings.aabbs = !debug_settings.aabbs,
+            Some(Button::Keyboard(Key::F5)) => debug_settings.centroids = !debug_settings.centroids,
+            Some(Button::Keyboard(Key::F6)) => debug_settings.grid = !debug_settings.grid,
+            Some(Button::Keyboard(Key::Space)) => controls.set_paused(!controls.paused, &app),
+            Some(Button::Keyboard(Key::N)) => controls.step(1, &app),
+            Some(Button::Keyboard(Key::M)) => controls.step(10, &app),
+            Some(Button::Keyboard(Key::Minus)) => controls.slower(&app),
+            Some(Button::Keyboard(Key::Equals)) => controls.faster(&app),
+            Some(Button::Keyboard(Key::R)) => app.send(SimCommand::Reset),
+            // L: reload the SVG scene after editing it
+            Some(Button::Keyboard(Key::L)) => {
+                if let Some(path) = &config.scene {
+                    match svg::load(path, &config.svg) {
+                        Ok((scene, skipped)) => {
+                            for message in skipped {
+                                eprintln!("warning: {}", message);
+                            }
+                            app.send(SimCommand::LoadScene(scene));
+                        }
+                        Err(e) => eprintln!("error: {}", e),
+                    }
+                }
+            }
+            // Enter closes the outline being drawn, Backspace drops its last vertex
+            Some(Button::Keyboard(Key::Return)) if controls.palette.drawing => {
+                match controls.palette.finish_outline() {
+                    Ok(command) => app.send(command),
+                    Err(e) => eprintln!("error: {}", e),
+                }
+            }
+            Some(Button::Keyboard(Key::Backspace)) => controls.palette.undo_vertex(),
+            _ => {}
         }
 
-        // Mouse release: stop dragging
-        if let Some(Button::Mouse(button)) = piston::ReleaseEvent::release_args(&event) {
-            if button == piston::MouseButton::Left {
+        // Mouse release: stop dragging or panning, or drop the body being placed
+        match piston::ReleaseEvent::release_args(&event) {
+            Some(Button::Mouse(piston::MouseButton::Left)) => {
+                if let Some(start) = input_handler.place_start.take() {
+                    app.send(controls.palette.place(start, input_handler.world_pos));
+                }
+                if input_handler.drag_command().is_some() {
+                    app.send(SimCommand::Release);
+                }
                 input_handler.handle_mouse_up();
+                ui.handle_mouse_up();
             }
+            Some(Button::Mouse(piston::MouseButton::Middle)) => input_handler.panning = false,
+            _ => {}
+        }
+
+        if let Some(args) = event.resize_args() {
+            window_size = args.window_size;
+            camera.viewport = window_size;
+            input_handler.update_world_pos(&camera);
+        }
+
+        // Mouse wheel: zoom about the cursor
+        if let Some([_, scroll]) = mouse::MouseScrollEvent::mouse_scroll_args(&event)
+            && !ui.wants_mouse()
+        {
+            camera.zoom_at(input_handler.mouse_pos, 1.1f64.powf(scroll));
+            input_handler.update_world_pos(&camera);
         }
 
-        // On render: drag logic + send updated softbody to simulation thread
+        // On render: draw the latest snapshot
         if let Some(_args) = event.render_args() {
-            if let Some(p) = from_sim_pause_rx.try_iter().last() {
-                pause = p;
-            }
+            let fresh = app.snapshots.update();
+            let softbodies = app.softbodies();
 
-            if let Some(mut softbodies) = from_sim_rx.try_iter().last() {
-                // Dragging logic
-                if input_handler.mouse_down {
-                    if let (Some(sb_idx), Some(pt_idx)) =
-                        (input_handler.softbody_index, input_handler.held_point_index)
-                    {
-                        if sb_idx < softbodies.softbodies.len()
-                            && pt_idx < softbodies.softbodies[sb_idx].points.len()
-                        {
-                            softbodies.softbodies[sb_idx].points[pt_idx].position =
-                                input_handler.mouse_pos;
-                        }
-                    }
-                }
+            if camera.follow.is_some() {
+                camera.update(softbodies);
+                input_handler.update_world_pos(&camera);
+            }
+            // Once per frame is enough to track the cursor, and also keeps the
+            // held point under it while the camera moves
+            if let Some(drag) = input_handler.drag_command() {
+                app.send(drag);
+            }
 
-                // Send the updated softbodies to the simulation thread
-                to_sim_tx.send(softbodies.clone()).unwrap();
+            ui.begin(window_size);
+            controls.show(&mut ui, &app, &mut render_settings);
+            ui.end();
 
-                // Draw
-                window.draw_2d(&event, |c, g, device| {
-                    piston_window::clear([0.1, 0.1, 0.3, 1.0], g);
-                    render::render_all_softbodies(c, g, &softbodies.softbodies);
-                    spawn_button.render(c, g, &mut glyphs);
-                    pause_button.render(c, g, &mut glyphs);
+            window.draw_2d(&event, |c, g, device| {
+                piston_window::clear([0.1, 0.1, 0.3, 1.0], g);
+                render::render_colliders(camera.apply(c), g, &softbodies.colliders);
+                render::render_rigid_bodies(camera.apply(c), g, &softbodies.rigid_bodies);
+                render::render_all_softbodies(
+                    camera.apply(c),
+                    g,
+                    &softbodies.softbodies,
+                    &render_settings,
+                );
+                render::render_fluids(camera.apply(c), g, &softbodies.fluids);
+                render::render_joints(camera.apply(c), g, softbodies);
+                render::render_fields(camera.apply(c), g, &softbodies.fields);
+                render::render_debug(camera.apply(c), g, softbodies, &debug_settings);
+                render::render_outline_draft(
+                    camera.apply(c),
+                    g,
+                    &controls.palette.outline,
+                    input_handler.world_pos,
+                );
+                if let Some((body, point)) = input_handler.joint_start
+                    && let Some(start) = softbodies
+                        .softbodies
+                        .get(body)
+                        .and_then(|sb| sb.points.get(point))
+                {
+                    render::render_placement(
+                        camera.apply(c),
+                        g,
+                        start.position,
+                        input_handler.world_pos,
+                    );
+                }
+                if let Some(start) = input_handler.place_start {
+                    render::render_placement(camera.apply(c), g, start, input_handler.world_pos);
+                }
+                render::render_legend(c, g, &mut glyphs, &render_settings, window_size);
+                ui.render(c, g, &mut glyphs);
 
-                    glyphs.factory.encoder.flush(device);
-                });
+                glyphs.factory.encoder.flush(device);
+            });
 
+            if fresh {
                 frame_count += 1;
-                if last_fps_check.elapsed().as_secs() >= 1 {
-                    let fps = frame_count as f64 / last_fps_check.elapsed().as_secs_f64();
-                    println!("FPS: {:.2}", fps);
-                    frame_count = 0;
-                    last_fps_check = Instant::now();
-                }
+            }
+            if last_fps_check.elapsed().as_secs() >= 1 {
+                let fps = frame_count as f64 / last_fps_check.elapsed().as_secs_f64();
+                println!("FPS: {:.2}", fps);
+                frame_count = 0;
+                last_fps_check = Instant::now();
             }
         }
     }
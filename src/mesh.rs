@@ -0,0 +1,798 @@
+// Turns outlines into triangle meshes so any shape can become a softbody.
+// Outlines (and holes) are sampled at a target spacing, the inside is filled
+// with Steiner points on a staggered grid, and everything is joined by a
+// constrained Delaunay triangulation: points are inserted one at a time and
+// edges flipped until the triangulation is Delaunay, then every boundary edge
+// it misses is recovered by flipping the edges that cross it. The mesh follows
+// the outline and holes without adding points to them. Every triangle edge
+// becomes a spring.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::broadphase::SpatialGrid;
+use crate::geometry;
+use crate::simulation::{Point, SoftBody, Spring};
+
+// Points closer than this fraction of the spacing are merged into one
+const MERGE_DISTANCE: f64 = 0.01;
+// Relative size below which an in-circle test counts as cocircular, so rounding
+// can't flip the same edge back and forth
+const COCIRCULAR: f64 = 1e-12;
+// marks a triangle edge on the outside of the triangulation
+const NONE: usize = usize::MAX;
+
+pub struct Mesh {
+    pub points: Vec<[f64; 2]>,
+    pub triangles: Vec<[usize; 3]>,
+    // indices of the points on the outline, in order
+    pub boundary: Vec<usize>,
+    // indices of the points around each hole
+    pub holes: Vec<Vec<usize>>,
+    // target distance between neighbouring points
+    pub spacing: f64,
+}
+
+impl Mesh {
+    /// Meshes the inside of a polygon with points roughly `spacing` apart.
+    pub fn from_outline(outline: &[[f64; 2]], spacing: f64) -> Result<Self, String> {
+        Mesh::from_polygon(outline, &[], spacing)
+    }
+
+    /// Meshes the area inside `outline` but outside every hole, with points
+    /// roughly `spacing` apart. Fails if the mesh can't be made to follow the
+    /// edges of the holes.
+    pub fn from_polygon(
+        outline: &[[f64; 2]],
+        holes: &[Vec<[f64; 2]>],
+        spacing: f64,
+    ) -> Result<Self, String> {
+        let inside = |p: [f64; 2]| {
+            geometry::contains(outline, p) && !holes.iter().any(|h| geometry::contains(h, p))
+        };
+
+        let samples: Vec<Vec<[f64; 2]>> = std::iter::once(outline)
+            .chain(holes.iter().map(Vec::as_slice))
+            .map(|ring| resample(ring, spacing))
+            .collect();
+        let min_clearance = spacing * 0.5;
+        let interior = interior_points(outline, spacing).into_iter().filter(|&p| {
+            inside(p)
+                && holes
+                    .iter()
+                    .all(|h| geometry::distance_to_outline(h, p) > min_clearance)
+        });
+
+        // Coincident points would give zero-area triangles, so points that
+        // land on one already there are merged into it
+        let candidates: Vec<[f64; 2]> = samples.iter().flatten().copied().chain(interior).collect();
+        let (points, merged) = merge_points(&candidates, spacing.max(0.0) * MERGE_DISTANCE);
+
+        let mut rings: Vec<Vec<usize>> = Vec::new();
+        let mut offset = 0;
+        for ring in &samples {
+            let mut indices = merged[offset..offset + ring.len()].to_vec();
+            offset += ring.len();
+            indices.dedup();
+            if indices.len() > 1 && indices.first() == indices.last() {
+                indices.pop();
+            }
+            rings.push(indices);
+        }
+
+        let mut triangulation = Triangulation::new(&points);
+        for ring in &rings {
+            for k in 0..ring.len() {
+                let (a, b) = (ring[k], ring[(k + 1) % ring.len()]);
+                if let Err(e) = triangulation.constrain(a, b) {
+                    // Only a self-intersecting outline gets here, and clipping
+                    // its ears still gives something to simulate
+                    if holes.is_empty() {
+                        return Ok(Mesh::ear_clipped(outline));
+                    }
+                    return Err(format!(
+                        "could not mesh a shape with {} hole(s): {}",
+                        holes.len(),
+                        e
+                    ));
+                }
+            }
+        }
+        triangulation.restore_delaunay();
+        let mut triangles = triangulation.triangles();
+
+        // Drop triangles that fill concave notches or holes
+        triangles.retain(|&[a, b, c]| {
+            inside([
+                (points[a][0] + points[b][0] + points[c][0]) / 3.0,
+                (points[a][1] + points[b][1] + points[c][1]) / 3.0,
+            ])
+        });
+
+        let boundary = rings.remove(0);
+        Ok(Mesh {
+            points,
+            triangles,
+            boundary,
+            holes: rings,
+            spacing,
+        })
+    }
+
+    /// Triangulates just the outline's own vertices by ear clipping, with no
+    /// interior points.
+    pub fn ear_clipped(outline: &[[f64; 2]]) -> Self {
+        let mut outline = outline.to_vec();
+        outline.dedup();
+        if outline.len() > 1 && outline.first() == outline.last() {
+            outline.pop();
+        }
+        let n = outline.len().max(1) as f64;
+        let perimeter: f64 = (0..outline.len())
+            .map(|i| {
+                let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+                (b[0] - a[0]).hypot(b[1] - a[1])
+            })
+            .sum();
+        Mesh {
+            points: outline.clone(),
+            triangles: geometry::triangulate(&outline),
+            boundary: (0..outline.len()).collect(),
+            holes: Vec::new(),
+            spacing: perimeter / n,
+        }
+    }
+
+    /// Every triangle edge once, as (lower index, higher index).
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = self
+            .triangles
+            .iter()
+            .flat_map(|&t| undirected_edges(t))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    pub fn to_softbody(&self) -> SoftBody {
+        let mut soft_body = SoftBody::new();
+        for &position in &self.points {
+            soft_body
+                .points
+                .push(Point::new(position, 1.0, self.spacing * 0.2));
+        }
+        for (a, b) in self.edges() {
+            let (pa, pb) = (self.points[a], self.points[b]);
+            let length = ((pb[0] - pa[0]).powi(2) + (pb[1] - pa[1]).powi(2)).sqrt();
+            soft_body.springs.push(Spring::new(a, b, length));
+        }
+        soft_body.boundary = self.boundary.clone();
+        soft_body.holes = self.holes.clone();
+        soft_body.triangles = self.triangles.clone();
+        soft_body.constrain_areas();
+        soft_body
+    }
+}
+
+/// Merges points no more than `distance` apart, keeping the first of each
+/// cluster. Returns the points kept and the index each input point became.
+fn merge_points(candidates: &[[f64; 2]], distance: f64) -> (Vec<[f64; 2]>, Vec<usize>) {
+    let mut grid = SpatialGrid::new();
+    grid.rebuild(
+        if distance > 0.0 { distance } else { 1.0 },
+        candidates.iter().enumerate().map(|(i, &p)| (0, i, p)),
+    );
+
+    let mut points = Vec::new();
+    let mut merged = Vec::with_capacity(candidates.len());
+    let mut kept = vec![false; candidates.len()];
+    for (i, &p) in candidates.iter().enumerate() {
+        let existing = grid
+            .near(p)
+            .map(|(_, j)| j)
+            .filter(|&j| {
+                let q = candidates[j];
+                j < i && kept[j] && (p[0] - q[0]).hypot(p[1] - q[1]) <= distance
+            })
+            .min();
+        match existing {
+            Some(j) => merged.push(merged[j]),
+            None => {
+                kept[i] = true;
+                merged.push(points.len());
+                points.push(p);
+            }
+        }
+    }
+    (points, merged)
+}
+
+/// Splits every edge of a closed polygon into pieces no longer than `spacing`.
+/// Without a positive spacing the polygon is left as it is.
+pub fn resample(polygon: &[[f64; 2]], spacing: f64) -> Vec<[f64; 2]> {
+    if spacing.is_nan() || spacing <= 0.0 {
+        return polygon.to_vec();
+    }
+    let n = polygon.len();
+    let mut points = Vec::new();
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        let length = ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+        let pieces = (length / spacing).ceil().max(1.0) as usize;
+        for k in 0..pieces {
+            let t = k as f64 / pieces as f64;
+            points.push([a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]);
+        }
+    }
+    points
+}
+
+/// Points on a staggered grid inside the polygon, kept away from its edges so
+/// they don't crowd the outline samples. Without a positive spacing there are
+/// none.
+pub fn interior_points(polygon: &[[f64; 2]], spacing: f64) -> Vec<[f64; 2]> {
+    if spacing.is_nan() || spacing <= 0.0 {
+        return Vec::new();
+    }
+    let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+    for p in polygon {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(p[axis]);
+            max[axis] = max[axis].max(p[axis]);
+        }
+    }
+
+    let row_height = spacing * 3f64.sqrt() / 2.0;
+    let mut points = Vec::new();
+    let mut y = min[1] + row_height / 2.0;
+    let mut row = 0;
+    while y < max[1] {
+        let mut x = min[0] + if row % 2 == 0 { 0.0 } else { spacing / 2.0 };
+        while x < max[0] {
+            let p = [x, y];
+            if geometry::contains(polygon, p)
+                && geometry::distance_to_outline(polygon, p) > spacing * 0.5
+            {
+                points.push(p);
+            }
+            x += spacing;
+        }
+        y += row_height;
+        row += 1;
+    }
+    points
+}
+
+fn undirected_edges([a, b, c]: [usize; 3]) -> [(usize, usize); 3] {
+    [(a, b), (b, c), (c, a)].map(|(a, b)| (a.min(b), a.max(b)))
+}
+
+/// Twice the signed area of the triangle `a b c`; positive when it turns
+/// counter-clockwise (on y-up axes).
+fn orient(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Whether `d` is clearly inside the circle through the counter-clockwise
+/// triangle `a b c`.
+fn in_circle(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> bool {
+    let [ax, ay] = [a[0] - d[0], a[1] - d[1]];
+    let [bx, by] = [b[0] - d[0], b[1] - d[1]];
+    let [cx, cy] = [c[0] - d[0], c[1] - d[1]];
+    let terms = [
+        (ax * ax + ay * ay) * (bx * cy - cx * by),
+        (bx * bx + by * by) * (cx * ay - ax * cy),
+        (cx * cx + cy * cy) * (ax * by - bx * ay),
+    ];
+    let scale: f64 = terms.iter().map(|t| t.abs()).sum();
+    terms.iter().sum::<f64>() > scale * COCIRCULAR
+}
+
+/// Whether segments `a b` and `c d` cross at a point inside both.
+fn crosses(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> bool {
+    orient(a, b, c) * orient(a, b, d) < 0.0 && orient(c, d, a) * orient(c, d, b) < 0.0
+}
+
+/// A triangulation that points are added to one at a time, with every triangle
+/// stored counter-clockwise. Edge `k` of a triangle is the one opposite its
+/// corner `k`, and `neighbours` holds the triangle on the other side of it.
+struct Triangulation {
+    // the points, followed by the corners of a triangle enclosing them all
+    vertices: Vec<[f64; 2]>,
+    triangles: Vec<[usize; 3]>,
+    neighbours: Vec<[usize; 3]>,
+    // a triangle touching each vertex, to start walks around it from
+    incident: Vec<usize>,
+    // edges that must stay, as (lower index, higher index)
+    constrained: HashSet<(usize, usize)>,
+}
+
+impl Triangulation {
+    /// Delaunay triangulation of `points`.
+    fn new(points: &[[f64; 2]]) -> Self {
+        // Start from one triangle large enough to hold every point
+        let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+        for p in points {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        if points.is_empty() {
+            (min, max) = ([0.0; 2], [0.0; 2]);
+        }
+        let size = (max[0] - min[0]).max(max[1] - min[1]).max(1.0) * 20.0;
+        let mid = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let n = points.len();
+        let mut vertices = points.to_vec();
+        vertices.push([mid[0] - size, mid[1] - size]);
+        vertices.push([mid[0] + size, mid[1] - size]);
+        vertices.push([mid[0], mid[1] + size]);
+
+        let mut triangulation = Triangulation {
+            vertices,
+            triangles: Vec::new(),
+            neighbours: Vec::new(),
+            incident: vec![0; n + 3],
+            constrained: HashSet::new(),
+        };
+        triangulation.push([n, n + 1, n + 2], [NONE; 3]);
+        let mut last = 0;
+        for i in 0..n {
+            last = triangulation.insert(i, last);
+        }
+        triangulation
+    }
+
+    /// The triangles between the points, leaving out the enclosing triangle.
+    fn triangles(&self) -> Vec<[usize; 3]> {
+        let n = self.vertices.len() - 3;
+        self.triangles
+            .iter()
+            .filter(|t| t.iter().all(|&v| v < n))
+            .copied()
+            .collect()
+    }
+
+    fn set(&mut self, t: usize, corners: [usize; 3], neighbours: [usize; 3]) {
+        self.triangles[t] = corners;
+        self.neighbours[t] = neighbours;
+        for v in corners {
+            self.incident[v] = t;
+        }
+    }
+
+    fn push(&mut self, corners: [usize; 3], neighbours: [usize; 3]) -> usize {
+        self.triangles.push(corners);
+        self.neighbours.push(neighbours);
+        let t = self.triangles.len() - 1;
+        self.set(t, corners, neighbours);
+        t
+    }
+
+    /// Points `t`'s neighbour's edge back at `new` instead of `old`.
+    fn relink(&mut self, t: usize, old: usize, new: usize) {
+        if t != NONE
+            && let Some(k) = self.neighbours[t].iter().position(|&n| n == old)
+        {
+            self.neighbours[t][k] = new;
+        }
+    }
+
+    /// Corner `k` of `t` and the two after it.
+    fn corners(&self, t: usize, k: usize) -> [usize; 3] {
+        let c = self.triangles[t];
+        [c[k], c[(k + 1) % 3], c[(k + 2) % 3]]
+    }
+
+    /// The triangle `p` lies in, walking there from `start`, and the edge it
+    /// lies on if any.
+    fn locate(&self, p: [f64; 2], start: usize) -> (usize, Option<usize>) {
+        let outside = |t: usize, k: usize| {
+            let [_, a, b] = self.corners(t, k);
+            orient(self.vertices[a], self.vertices[b], p)
+        };
+        let mut t = start;
+        // Rotating which edge is tried first keeps the walk from circling
+        let mut found = false;
+        for step in 0..self.triangles.len() {
+            match (0..3)
+                .map(|i| (i + step) % 3)
+                .find(|&k| outside(t, k) < 0.0)
+            {
+                Some(k) if self.neighbours[t][k] != NONE => t = self.neighbours[t][k],
+                Some(_) => break,
+                None => {
+                    found = true;
+                    break;
+                }
+            }
+        }
+        if !found
+            && let Some(u) =
+                (0..self.triangles.len()).find(|&u| (0..3).all(|k| outside(u, k) >= 0.0))
+        {
+            t = u;
+        }
+        (t, (0..3).find(|&k| outside(t, k) == 0.0))
+    }
+
+    /// Adds vertex `i`, starting the search for it at triangle `start`, and
+    /// returns a triangle touching it.
+    fn insert(&mut self, i: usize, start: usize) -> usize {
+        let p = self.vertices[i];
+        let (t, on_edge) = self.locate(p, start);
+        let mut stack = Vec::new();
+        match on_edge {
+            Some(k) => {
+                // Split both triangles either side of the edge `b c` in two
+                let [a, b, c] = self.corners(t, k);
+                let outer = self.neighbours[t];
+                let (ab, ca) = (outer[(k + 2) % 3], outer[(k + 1) % 3]);
+                let u = outer[k];
+                let t1 = self.push([i, a, b], [ab, NONE, t]);
+                self.set(t, [i, c, a], [ca, t1, NONE]);
+                self.relink(ab, t, t1);
+                stack.extend([(t, 0), (t1, 0)]);
+                if u != NONE {
+                    let j =
+                        (0..3).find(|&j| self.triangles[u][j] != b && self.triangles[u][j] != c);
+                    let j = j.unwrap_or(0);
+                    let d = self.triangles[u][j];
+                    let (bd, dc) = (
+                        self.neighbours[u][(j + 1) % 3],
+                        self.neighbours[u][(j + 2) % 3],
+                    );
+                    let u1 = self.push([i, d, c], [dc, t, u]);
+                    self.set(u, [i, b, d], [bd, u1, t1]);
+                    self.relink(dc, u, u1);
+                    self.neighbours[t1][1] = u;
+                    self.neighbours[t][2] = u1;
+                    stack.extend([(u, 0), (u1, 0)]);
+                }
+            }
+            None => {
+                // Split the triangle into three around the new point
+                let [a, b, c] = self.triangles[t];
+                let [bc, ca, ab] = self.neighbours[t];
+                let t1 = self.push([i, c, a], [ca, NONE, t]);
+                let t2 = self.push([i, a, b], [ab, t, t1]);
+                self.set(t, [i, b, c], [bc, t1, t2]);
+                self.neighbours[t1][1] = t2;
+                self.relink(ca, t, t1);
+                self.relink(ab, t, t2);
+                stack.extend([(t, 0), (t1, 0), (t2, 0)]);
+            }
+        }
+        self.legalize(stack);
+        self.incident[i]
+    }
+
+    /// Replaces edge `k` of `t`, the diagonal of the quadrilateral `t` makes
+    /// with its neighbour, with the other diagonal. Afterwards `t` is
+    /// `[a, b, d]` and the neighbour `[a, d, c]`, where `a` was corner `k` of
+    /// `t` and `d` the neighbour's far corner.
+    fn flip(&mut self, t: usize, k: usize) {
+        let [a, b, c] = self.corners(t, k);
+        let u = self.neighbours[t][k];
+        let j = (0..3)
+            .find(|&j| self.triangles[u][j] != b && self.triangles[u][j] != c)
+            .unwrap();
+        let d = self.triangles[u][j];
+        let (ca, ab) = (
+            self.neighbours[t][(k + 1) % 3],
+            self.neighbours[t][(k + 2) % 3],
+        );
+        let (bd, dc) = (
+            self.neighbours[u][(j + 1) % 3],
+            self.neighbours[u][(j + 2) % 3],
+        );
+        self.set(t, [a, b, d], [bd, u, ab]);
+        self.set(u, [a, d, c], [dc, ca, t]);
+        self.relink(bd, u, t);
+        self.relink(ca, t, u);
+    }
+
+    /// The far corner of `t`'s neighbour across edge `k`, if it has one.
+    fn across(&self, t: usize, k: usize) -> Option<usize> {
+        let u = self.neighbours[t][k];
+        if u == NONE {
+            return None;
+        }
+        let [_, b, c] = self.corners(t, k);
+        self.triangles[u].into_iter().find(|&v| v != b && v != c)
+    }
+
+    /// Whether flipping edge `k` of `t` leaves two proper triangles.
+    fn can_flip(&self, t: usize, k: usize) -> bool {
+        let [a, b, c] = self.corners(t, k).map(|v| self.vertices[v]);
+        match self.across(t, k) {
+            Some(d) => crosses(a, self.vertices[d], b, c),
+            None => false,
+        }
+    }
+
+    /// Flips edges, starting from the `(triangle, edge)` pairs in `stack`,
+    /// until none of them has a point inside a neighbouring circumcircle.
+    /// Constrained edges are left alone.
+    fn legalize(&mut self, mut stack: Vec<(usize, usize)>) {
+        while let Some((t, k)) = stack.pop() {
+            let [a, b, c] = self.corners(t, k);
+            let Some(d) = self.across(t, k) else {
+                continue;
+            };
+            if self.constrained.contains(&(b.min(c), b.max(c)))
+                || !in_circle(
+                    self.vertices[a],
+                    self.vertices[b],
+                    self.vertices[c],
+                    self.vertices[d],
+                )
+                || !self.can_flip(t, k)
+            {
+                continue;
+            }
+            let u = self.neighbours[t][k];
+            self.flip(t, k);
+            stack.extend([(t, 0), (t, 2), (u, 0), (u, 1)]);
+        }
+    }
+
+    /// Makes the triangulation Delaunay again everywhere but across
+    /// constrained edges.
+    fn restore_delaunay(&mut self) {
+        let stack = (0..self.triangles.len())
+            .flat_map(|t| (0..3).map(move |k| (t, k)))
+            .collect();
+        self.legalize(stack);
+    }
+
+    /// The triangle and edge index holding the edge from `a` to `b`, found by
+    /// turning around `a`.
+    fn find_edge(&self, a: usize, b: usize) -> Option<(usize, usize)> {
+        let start = self.incident[a];
+        let mut t = start;
+        loop {
+            let i = self.triangles[t].iter().position(|&v| v == a)?;
+            if self.triangles[t][(i + 1) % 3] == b {
+                return Some((t, (i + 2) % 3));
+            }
+            t = self.neighbours[t][(i + 1) % 3];
+            if t == NONE || t == start {
+                return None;
+            }
+        }
+    }
+
+    /// Makes `s e` an edge of the triangulation by flipping the edges that
+    /// cross it, and keeps it there.
+    fn constrain(&mut self, s: usize, e: usize) -> Result<(), String> {
+        if s == e {
+            return Ok(());
+        }
+        self.constrained.insert((s.min(e), s.max(e)));
+        if self.find_edge(s, e).is_some() || self.find_edge(e, s).is_some() {
+            return Ok(());
+        }
+        let (ps, pe) = (self.vertices[s], self.vertices[e]);
+        let blocked = || {
+            format!(
+                "the edge from ({:.1}, {:.1}) to ({:.1}, {:.1}) runs into another",
+                ps[0], ps[1], pe[0], pe[1]
+            )
+        };
+
+        // Find the triangle around `s` that the edge leaves through, then walk
+        // along the edge collecting every edge it crosses
+        let start = self.incident[s];
+        let mut t = start;
+        let (x, y) = loop {
+            let i = self.triangles[t]
+                .iter()
+                .position(|&v| v == s)
+                .ok_or_else(blocked)?;
+            let [_, x, y] = self.corners(t, i);
+            if orient(ps, pe, self.vertices[x]) < 0.0 && orient(ps, pe, self.vertices[y]) > 0.0 {
+                break (x, y);
+            }
+            t = self.neighbours[t][(i + 1) % 3];
+            if t == NONE || t == start {
+                return Err(blocked());
+            }
+        };
+        let mut crossing = VecDeque::new();
+        let (mut x, mut y) = (x, y);
+        loop {
+            if self.constrained.contains(&(x.min(y), x.max(y))) {
+                return Err(blocked());
+            }
+            crossing.push_back((x, y));
+            let k = (0..3)
+                .find(|&k| self.triangles[t][k] != x && self.triangles[t][k] != y)
+                .ok_or_else(blocked)?;
+            let v = self.across(t, k).ok_or_else(blocked)?;
+            t = self.neighbours[t][k];
+            if v == e {
+                break;
+            }
+            let side = orient(ps, pe, self.vertices[v]);
+            if side < 0.0 {
+                x = v;
+            } else if side > 0.0 {
+                y = v;
+            } else {
+                return Err(blocked());
+            }
+        }
+
+        // Flip crossing edges away, putting back any whose quadrilateral isn't
+        // convex yet or whose replacement still crosses; some flip always works
+        let mut attempts = crossing.len() * crossing.len() + 16;
+        while let Some((x, y)) = crossing.pop_front() {
+            attempts = attempts.checked_sub(1).ok_or_else(blocked)?;
+            let (t, k) = self.find_edge(x, y).ok_or_else(blocked)?;
+            if !self.can_flip(t, k) {
+                crossing.push_back((x, y));
+                continue;
+            }
+            self.flip(t, k);
+            let [a, _, d] = self.triangles[t];
+            if crosses(ps, pe, self.vertices[a], self.vertices[d]) {
+                crossing.push_back((a, d));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [[f64; 2]; 4] = [[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0]];
+
+    const L_SHAPE: [[f64; 2]; 6] = [
+        [0.0, 0.0],
+        [100.0, 0.0],
+        [100.0, 40.0],
+        [40.0, 40.0],
+        [40.0, 100.0],
+        [0.0, 100.0],
+    ];
+
+    fn triangle_area(mesh: &Mesh, [a, b, c]: [usize; 3]) -> f64 {
+        geometry::signed_area(&[mesh.points[a], mesh.points[b], mesh.points[c]]).abs()
+    }
+
+    /// Checks the mesh covers `area` with proper triangles and keeps every
+    /// edge of its outline and holes.
+    fn assert_fits(mesh: &Mesh, area: f64) {
+        let covered: f64 = mesh.triangles.iter().map(|&t| triangle_area(mesh, t)).sum();
+        assert!(
+            (covered - area).abs() < 1e-6 * area,
+            "{} != {}",
+            covered,
+            area
+        );
+        assert!(
+            mesh.triangles
+                .iter()
+                .all(|&t| triangle_area(mesh, t) > 1e-9)
+        );
+
+        let edges = mesh.edges();
+        for ring in std::iter::once(&mesh.boundary).chain(&mesh.holes) {
+            for k in 0..ring.len() {
+                let (a, b) = (ring[k], ring[(k + 1) % ring.len()]);
+                assert!(edges.contains(&(a.min(b), a.max(b))));
+            }
+        }
+    }
+
+    #[test]
+    fn meshes_a_convex_square() {
+        let mesh = Mesh::from_outline(&SQUARE, 20.0).unwrap();
+        assert_fits(&mesh, 100.0 * 100.0);
+        assert_eq!(mesh.boundary.len(), 20);
+        assert!(mesh.points.len() > mesh.boundary.len());
+    }
+
+    #[test]
+    fn meshes_a_concave_l() {
+        let mesh = Mesh::from_outline(&L_SHAPE, 15.0).unwrap();
+        assert_fits(&mesh, 100.0 * 100.0 - 60.0 * 60.0);
+    }
+
+    #[test]
+    fn meshes_around_a_hole() {
+        let hole = vec![[30.0, 30.0], [70.0, 30.0], [70.0, 70.0], [30.0, 70.0]];
+        let mesh = Mesh::from_polygon(&SQUARE, &[hole], 10.0).unwrap();
+        assert_fits(&mesh, 100.0 * 100.0 - 40.0 * 40.0);
+        assert_eq!(mesh.holes.len(), 1);
+    }
+
+    #[test]
+    fn recovers_edges_without_adding_points() {
+        // The chevron's inner corner and the small hole sit either side of
+        // its long edges, so Delaunay alone cuts across them
+        let holes = vec![
+            vec![[20.0, 40.0], [80.0, 50.0], [20.0, 60.0], [70.0, 50.0]],
+            vec![[45.0, 33.0], [55.0, 33.0], [50.0, 37.0]],
+        ];
+        let hole_area: f64 = holes.iter().map(|h| geometry::signed_area(h).abs()).sum();
+        for spacing in [100.0, 25.0, 10.0] {
+            let mesh = Mesh::from_polygon(&SQUARE, &holes, spacing).unwrap();
+            assert_fits(&mesh, 100.0 * 100.0 - hole_area);
+            assert_eq!(mesh.boundary.len(), resample(&SQUARE, spacing).len());
+            for (ring, hole) in mesh.holes.iter().zip(&holes) {
+                assert_eq!(ring.len(), resample(hole, spacing).len());
+            }
+        }
+    }
+
+    #[test]
+    fn unconstrained_edges_are_delaunay() {
+        let mesh = Mesh::from_outline(&L_SHAPE, 10.0).unwrap();
+        // Ear clipping would have left out the interior points
+        assert!(mesh.points.len() > mesh.boundary.len());
+
+        let ring = &mesh.boundary;
+        let on_outline = |(a, b): (usize, usize)| {
+            (0..ring.len()).any(|k| {
+                let (c, d) = (ring[k], ring[(k + 1) % ring.len()]);
+                (c.min(d), c.max(d)) == (a, b)
+            })
+        };
+        for &t in &mesh.triangles {
+            let [a, b, c] = t.map(|v| mesh.points[v]);
+            let [a, b, c] = if orient(a, b, c) > 0.0 {
+                [a, b, c]
+            } else {
+                [a, c, b]
+            };
+            for &u in &mesh.triangles {
+                let shared: Vec<_> = undirected_edges(u)
+                    .into_iter()
+                    .filter(|e| undirected_edges(t).contains(e))
+                    .collect();
+                if let [edge] = shared[..]
+                    && !on_outline(edge)
+                {
+                    let far = u.into_iter().find(|v| !t.contains(v)).unwrap();
+                    assert!(!in_circle(a, b, c, mesh.points[far]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn merges_duplicate_points() {
+        let repeated = [
+            [0.0, 0.0],
+            [0.0, 0.0],
+            [100.0, 0.0],
+            [100.0, 100.0],
+            [100.0, 100.0],
+            [0.0, 100.0],
+            [0.0, 0.0],
+        ];
+        let mesh = Mesh::from_outline(&repeated, 20.0).unwrap();
+        let clean = Mesh::from_outline(&SQUARE, 20.0).unwrap();
+        assert_eq!(mesh.points.len(), clean.points.len());
+        assert_fits(&mesh, 100.0 * 100.0);
+
+        let clipped = Mesh::ear_clipped(&repeated);
+        assert_eq!(clipped.points.len(), 4);
+        assert_eq!(clipped.triangles.len(), 2);
+    }
+
+    #[test]
+    fn spacing_must_be_positive() {
+        assert_eq!(resample(&SQUARE, 0.0), SQUARE.to_vec());
+        assert_eq!(resample(&SQUARE, -5.0), SQUARE.to_vec());
+        assert!(interior_points(&SQUARE, 0.0).is_empty());
+        assert!(interior_points(&SQUARE, -5.0).is_empty());
+        assert_eq!(resample(&SQUARE, 50.0).len(), 8);
+    }
+}
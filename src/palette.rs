@@ -0,0 +1,417 @@
+use crate::command::SimCommand;
+use crate::field::{Attractor, Drag, Field, Region, Uniform, Vortex, Wind};
+use crate::fluid::Fluid;
+use crate::geometry;
+use crate::mesh::Mesh;
+use crate::rigid::{self, RigidBody};
+use crate::simulation::{self, JointKind, MATERIALS, SoftBody};
+
+// Initial velocity per world unit of the placement drag
+const FLING_SCALE: f64 = 0.05;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shape {
+    Square,
+    Rectangle,
+    Circle,
+    Ring,
+    Blob,
+    Rope,
+    Triangle,
+    Polygon,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 8] = [
+        Shape::Square,
+        Shape::Rectangle,
+        Shape::Circle,
+        Shape::Ring,
+        Shape::Blob,
+        Shape::Rope,
+        Shape::Triangle,
+        Shape::Polygon,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Shape::Square => "Square",
+            Shape::Rectangle => "Rectangle",
+            Shape::Circle => "Circle",
+            Shape::Ring => "Ring",
+            Shape::Blob => "Blob",
+            Shape::Rope => "Rope",
+            Shape::Triangle => "Triangle",
+            Shape::Polygon => "Polygon",
+        }
+    }
+}
+
+/// Force fields that can be dropped into the scene, each acting within the
+/// palette's size of where it's dropped.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    Attractor,
+    Repeller,
+    Vortex,
+    Wind,
+    Drag,
+    Updraft,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 6] = [
+        FieldKind::Attractor,
+        FieldKind::Repeller,
+        FieldKind::Vortex,
+        FieldKind::Wind,
+        FieldKind::Drag,
+        FieldKind::Updraft,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FieldKind::Attractor => "Attractor",
+            FieldKind::Repeller => "Repeller",
+            FieldKind::Vortex => "Vortex",
+            FieldKind::Wind => "Wind",
+            FieldKind::Drag => "Drag",
+            FieldKind::Updraft => "Updraft",
+        }
+    }
+}
+
+/// What the next spawned body looks like, and whether clicks in the scene
+/// place it.
+pub struct Palette {
+    pub shape: Shape,
+    pub size: f64,
+    // grid cells, segments or polygon sides depending on the shape
+    pub resolution: f64,
+    pub material: usize,
+    // build bodies from finite elements rather than springs
+    pub fem: bool,
+    // spawn rigid bodies where the shape has one; ropes, rings and blobs stay soft
+    pub rigid: bool,
+    // pin rigid bodies where they're placed, as pivots and ledges
+    pub fixed: bool,
+    // let the air push on the body's edges, for sails, flags and leaves
+    pub aerodynamic: bool,
+    // multiplier on the world's gravity for new bodies
+    pub gravity_scale: f64,
+    // kind of joint the J key makes
+    pub joint: JointKind,
+    // force field the Q key drops under the cursor
+    pub field: FieldKind,
+    // fields act over the whole world rather than around where they're dropped
+    pub field_everywhere: bool,
+    pub placing: bool,
+    // clicks in the scene add vertices to `outline` instead of picking points
+    pub drawing: bool,
+    pub outline: Vec<[f64; 2]>,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Palette {
+            shape: Shape::Square,
+            size: 100.0,
+            resolution: 4.0,
+            material: 0,
+            fem: false,
+            rigid: false,
+            fixed: false,
+            aerodynamic: false,
+            gravity_scale: 1.0,
+            joint: JointKind::Distance,
+            field: FieldKind::Attractor,
+            field_everywhere: false,
+            placing: false,
+            drawing: false,
+            outline: Vec::new(),
+        }
+    }
+
+    /// The command that spawns the selected shape centred on `center`, moving
+    /// at `velocity`.
+    pub fn spawn(&self, center: [f64; 2], velocity: [f64; 2]) -> SimCommand {
+        if self.rigid
+            && let Some(body) = self.build_rigid(center)
+        {
+            return SimCommand::SpawnRigid(self.finish_rigid(body.with_velocity(velocity)));
+        }
+        SimCommand::Spawn(self.build(center).with_velocity(velocity))
+    }
+
+    /// The rigid version of the selected shape, if it has one.
+    fn build_rigid(&self, center: [f64; 2]) -> Option<RigidBody> {
+        let size = self.size;
+        let resolution = self.resolution.round().max(1.0) as usize;
+        match self.shape {
+            Shape::Square => Some(RigidBody::new_box(center, size, size)),
+            Shape::Rectangle => Some(RigidBody::new_box(center, size, size / 2.0)),
+            Shape::Circle => Some(RigidBody::new_circle(center, size / 2.0)),
+            Shape::Triangle => Some(RigidBody::new_regular_polygon(center, size / 2.0, 3)),
+            Shape::Polygon => Some(RigidBody::new_regular_polygon(
+                center,
+                size / 2.0,
+                resolution,
+            )),
+            Shape::Ring | Shape::Blob | Shape::Rope => None,
+        }
+    }
+
+    fn finish_rigid(&self, body: RigidBody) -> RigidBody {
+        let material = &MATERIALS[self.material];
+        let body = body
+            .with_density(rigid::DEFAULT_DENSITY * material.mass)
+            .with_color(material.color)
+            .with_gravity_scale(self.gravity_scale);
+        if self.fixed { body.with_static() } else { body }
+    }
+
+    /// The command that adds the selected force field around `center`, or
+    /// centred there but acting everywhere if `field_everywhere` is set.
+    pub fn field_at(&self, center: [f64; 2]) -> SimCommand {
+        let radius = self.size;
+        let circle = Region::Circle { center, radius };
+        let (field, region) = match self.field {
+            FieldKind::Attractor => (
+                Field::new(Attractor {
+                    center,
+                    strength: 0.3,
+                    radius: radius / 2.0,
+                }),
+                circle,
+            ),
+            FieldKind::Repeller => (
+                Field::new(Attractor {
+                    center,
+                    strength: -0.3,
+                    radius: radius / 2.0,
+                }),
+                circle,
+            ),
+            FieldKind::Vortex => (
+                Field::new(Vortex {
+                    center,
+                    strength: 0.2,
+                    radius: radius / 2.0,
+                }),
+                circle,
+            ),
+            FieldKind::Wind => (
+                Field::new(Wind {
+                    velocity: [3.0, 0.0],
+                    drag: 0.05,
+                    turbulence: 3.0,
+                    scale: radius,
+                }),
+                circle,
+            ),
+            // twice default gravity, so whatever drifts in rises
+            FieldKind::Updraft => (
+                Field::new(Uniform {
+                    acceleration: [0.0, -2.0 * simulation::DEFAULT_GRAVITY[1]],
+                }),
+                circle,
+            ),
+            // a slab of thick fluid rather than a blob of it
+            FieldKind::Drag => {
+                let region = Region::Rect([
+                    center[0] - radius,
+                    center[1] - radius / 2.0,
+                    center[0] + radius,
+                    center[1] + radius / 2.0,
+                ]);
+                (Field::new(Drag { coefficient: 0.1 }), region)
+            }
+        };
+        let region = if self.field_everywhere {
+            Region::Everywhere
+        } else {
+            region
+        };
+        SimCommand::AddField(field.with_region(region))
+    }
+
+    /// The command that adds a tank of water centred on `center`, twice the
+    /// palette's size across and as deep as the size.
+    pub fn tank_at(&self, center: [f64; 2]) -> SimCommand {
+        let size = self.size;
+        SimCommand::AddFluid(Fluid::new_rect([
+            center[0] - size,
+            center[1] - size / 2.0,
+            center[0] + size,
+            center[1] + size / 2.0,
+        ]))
+    }
+
+    /// Builds the selected shape centred on `center`.
+    pub fn build(&self, center: [f64; 2]) -> SoftBody {
+        let size = self.size;
+        let resolution = self.resolution.round().max(1.0) as usize;
+        let body = match self.shape {
+            Shape::Square => SoftBody::new_square(
+                [center[0] - size / 2.0, center[1] - size / 2.0],
+                size,
+                resolution as i32,
+            ),
+            Shape::Rectangle => SoftBody::new_rectangle(
+                [center[0] - size / 2.0, center[1] - size / 4.0],
+                size,
+                size / 2.0,
+                resolution as i32,
+                resolution.div_ceil(2) as i32,
+            ),
+            Shape::Circle => SoftBody::new_circle(center, size / 2.0, resolution * 4),
+            Shape::Ring => SoftBody::new_ring(center, size / 4.0, size / 2.0, resolution * 4),
+            Shape::Blob => SoftBody::new_blob(center, size / 2.0, resolution * 4),
+            Shape::Rope => SoftBody::new_rope(
+                [center[0] - size / 2.0, center[1]],
+                [center[0] + size / 2.0, center[1]],
+                resolution * 2,
+            ),
+            Shape::Triangle => SoftBody::new_regular_polygon(center, size / 2.0, 3),
+            Shape::Polygon => SoftBody::new_regular_polygon(center, size / 2.0, resolution),
+        };
+        self.finish(body)
+    }
+
+    fn finish(&self, body: SoftBody) -> SoftBody {
+        let material = &MATERIALS[self.material];
+        let mut body = body
+            .with_material(material)
+            .with_gravity_scale(self.gravity_scale);
+        if self.fem {
+            body = body.with_fem(material);
+        }
+        if self.aerodynamic {
+            body = body.with_aerodynamics(simulation::DEFAULT_DRAG, simulation::DEFAULT_LIFT);
+        }
+        body
+    }
+
+    fn spacing(&self) -> f64 {
+        self.size / self.resolution.max(1.0)
+    }
+
+    /// Adds a vertex to the outline being drawn. Clicking within `close_radius`
+    /// of the first vertex closes the outline and returns the command that
+    /// spawns it.
+    pub fn add_vertex(
+        &mut self,
+        position: [f64; 2],
+        close_radius: f64,
+    ) -> Result<Option<SimCommand>, String> {
+        if let Some(first) = self.outline.first()
+            && self.outline.len() >= 3
+            && (position[0] - first[0]).hypot(position[1] - first[1]) < close_radius
+        {
+            return self.finish_outline().map(Some);
+        }
+        self.outline.push(position);
+        Ok(None)
+    }
+
+    pub fn undo_vertex(&mut self) {
+        self.outline.pop();
+    }
+
+    /// Meshes the drawn outline into a body. The outline is discarded either
+    /// way; an error says why it couldn't be spawned, e.g. because it encloses
+    /// no area worth simulating. Rigid bodies take the outline's convex hull.
+    pub fn finish_outline(&mut self) -> Result<SimCommand, String> {
+        let outline = std::mem::take(&mut self.outline);
+        let spacing = self.spacing();
+        if outline.len() < 3 {
+            return Err(format!(
+                "an outline needs at least 3 vertices, got {}",
+                outline.len()
+            ));
+        }
+        if geometry::signed_area(&outline).abs() < spacing * spacing {
+            return Err("the outline encloses too little area to simulate".to_string());
+        }
+        if self.rigid {
+            // Notches in a drawn outline are filled in; rigid bodies are convex
+            let body = self.finish_rigid(RigidBody::new_polygon(&outline));
+            return Ok(SimCommand::SpawnRigid(body));
+        }
+        let mesh = Mesh::from_outline(&outline, spacing)?;
+        Ok(SimCommand::Spawn(self.finish(mesh.to_softbody())))
+    }
+
+    /// Spawns the selected shape where a placement drag started, moving in the
+    /// direction it was dragged.
+    pub fn place(&self, start: [f64; 2], end: [f64; 2]) -> SimCommand {
+        self.spawn(
+            start,
+            [
+                (end[0] - start[0]) * FLING_SCALE,
+                (end[1] - start[1]) * FLING_SCALE,
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_can_act_everywhere() {
+        let mut palette = Palette::new();
+        palette.field = FieldKind::Wind;
+        let SimCommand::AddField(field) = palette.field_at([100.0, 100.0]) else {
+            panic!("field_at should add a field");
+        };
+        assert!(
+            field.region
+                == Region::Circle {
+                    center: [100.0, 100.0],
+                    radius: palette.size
+                }
+        );
+
+        palette.field_everywhere = true;
+        let SimCommand::AddField(field) = palette.field_at([100.0, 100.0]) else {
+            panic!("field_at should add a field");
+        };
+        assert!(field.region == Region::Everywhere);
+    }
+
+    #[test]
+    fn fixed_rigid_bodies_stay_where_they_are_placed() {
+        let mut palette = Palette::new();
+        palette.rigid = true;
+        palette.fixed = true;
+        let SimCommand::SpawnRigid(body) = palette.place([100.0, 100.0], [150.0, 100.0]) else {
+            panic!("a square has a rigid version");
+        };
+        assert!(body.is_static());
+        assert_eq!(body.velocity, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn drawn_outlines_are_meshed_or_dropped() {
+        let mut palette = Palette::new();
+        for p in [[0.0, 0.0], [200.0, 0.0], [200.0, 100.0], [100.0, 40.0]] {
+            assert!(palette.add_vertex(p, 10.0).unwrap().is_none());
+        }
+        let Ok(Some(SimCommand::Spawn(body))) = palette.add_vertex([3.0, 4.0], 10.0) else {
+            panic!("closing the outline should spawn a softbody");
+        };
+        assert!(palette.outline.is_empty());
+        assert_eq!(body.boundary.first(), Some(&0));
+        assert!(body.points.len() > 4);
+
+        // A sliver with next to no area isn't worth simulating
+        for p in [[0.0, 0.0], [200.0, 0.0], [200.0, 1.0]] {
+            palette.add_vertex(p, 10.0).unwrap();
+        }
+        assert!(palette.finish_outline().is_err());
+        assert!(palette.outline.is_empty());
+        assert!(palette.finish_outline().is_err());
+    }
+}
@@ -1,20 +1,277 @@
 use piston_window::*;
 
-use crate::simulation;
+use crate::collider::Collider;
+use crate::colormap;
+use crate::field::{Field, Region};
+use crate::fluid::Fluid;
+use crate::geometry;
+use crate::rigid::{RigidBody, RigidShape};
+use crate::simulation::{self, JointKind};
 
-pub fn render_all_softbodies(c: Context, g: &mut G2d, softbodies: &[simulation::SoftBody]) {
+/// Quantity the springs are colored by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HeatmapMode {
+    Off,
+    Strain,
+    Force,
+    Plastic,
+}
+
+impl HeatmapMode {
+    pub const ALL: [HeatmapMode; 4] = [
+        HeatmapMode::Off,
+        HeatmapMode::Strain,
+        HeatmapMode::Force,
+        HeatmapMode::Plastic,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            HeatmapMode::Off => HeatmapMode::Strain,
+            HeatmapMode::Strain => HeatmapMode::Force,
+            HeatmapMode::Force => HeatmapMode::Plastic,
+            HeatmapMode::Plastic => HeatmapMode::Off,
+        }
+    }
+
+    pub fn default_range(self) -> f64 {
+        match self {
+            HeatmapMode::Off => 1.0,
+            HeatmapMode::Strain => 0.2,
+            HeatmapMode::Force => 2.0,
+            HeatmapMode::Plastic => 0.2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HeatmapMode::Off => "Off",
+            HeatmapMode::Strain => "Strain",
+            HeatmapMode::Force => "Force",
+            HeatmapMode::Plastic => "Plastic strain",
+        }
+    }
+
+    // Signed quantities are centered on zero: blue for compression, red for tension
+    fn diverging(self) -> bool {
+        self != HeatmapMode::Force
+    }
+
+    fn color(self, value: f64, range: f64) -> [f32; 4] {
+        if self.diverging() {
+            colormap::diverging(value / range)
+        } else {
+            colormap::viridis(value / range)
+        }
+    }
+}
+
+pub struct RenderSettings {
+    pub fill: bool,
+    pub outline: bool,
+    // Chaikin iterations applied to the outline before drawing
+    pub smoothing: u32,
+    // springs and points, drawn over the skin
+    pub wireframe: bool,
+    // springs are drawn whenever a heatmap is shown, wireframe or not
+    pub heatmap: HeatmapMode,
+    // value mapped to the end of the colormap (and its negative to the start)
+    pub heatmap_range: f64,
+}
+
+impl RenderSettings {
+    pub fn new() -> Self {
+        RenderSettings {
+            fill: true,
+            outline: true,
+            smoothing: 2,
+            wireframe: false,
+            heatmap: HeatmapMode::Off,
+            heatmap_range: HeatmapMode::Off.default_range(),
+        }
+    }
+
+    pub fn set_heatmap(&mut self, mode: HeatmapMode) {
+        self.heatmap = mode;
+        self.heatmap_range = mode.default_range();
+    }
+
+    pub fn cycle_heatmap(&mut self) {
+        self.set_heatmap(self.heatmap.next());
+    }
+}
+
+/// Diagnostic layers drawn over the scene, each toggled with a function key.
+pub struct DebugSettings {
+    pub velocities: bool,
+    pub forces: bool,
+    pub contacts: bool,
+    pub aabbs: bool,
+    pub centroids: bool,
+    pub grid: bool,
+}
+
+impl DebugSettings {
+    pub fn new() -> Self {
+        DebugSettings {
+            velocities: false,
+            forces: false,
+            contacts: false,
+            aabbs: false,
+            centroids: false,
+            grid: false,
+        }
+    }
+}
+
+pub fn render_all_softbodies(
+    c: Context,
+    g: &mut G2d,
+    softbodies: &[simulation::SoftBody],
+    settings: &RenderSettings,
+) {
     for softbody in softbodies {
-        render_softbody(c, g, softbody);
+        render_softbody(c, g, softbody, settings);
+    }
+}
+
+/// Static geometry, drawn in a flat grey under the softbodies.
+pub fn render_colliders(c: Context, g: &mut G2d, colliders: &[Collider]) {
+    for collider in colliders {
+        let outline = &collider.outline;
+        for [i, j, k] in geometry::triangulate(outline) {
+            polygon(
+                [0.45, 0.45, 0.5, 1.0],
+                &[outline[i], outline[j], outline[k]],
+                c.transform,
+                g,
+            );
+        }
+    }
+}
+
+/// Rigid bodies in their own color, with a darker edge. Circles get a spoke so
+/// their spin shows.
+pub fn render_rigid_bodies(c: Context, g: &mut G2d, bodies: &[RigidBody]) {
+    for body in bodies {
+        let edge = [
+            body.color[0] * 0.5,
+            body.color[1] * 0.5,
+            body.color[2] * 0.5,
+            1.0,
+        ];
+        match &body.shape {
+            RigidShape::Circle(radius) => {
+                let [x, y] = body.position;
+                let bounds = ellipse::circle(x, y, *radius);
+                Ellipse::new(body.color)
+                    .border(ellipse::Border {
+                        color: edge,
+                        radius: 1.0,
+                    })
+                    .draw(bounds, &c.draw_state, c.transform, g);
+                let (sin, cos) = body.angle.sin_cos();
+                line(
+                    edge,
+                    1.0,
+                    [x, y, x + cos * radius, y + sin * radius],
+                    c.transform,
+                    g,
+                );
+            }
+            RigidShape::Polygon(_) => {
+                let vertices = body.vertices();
+                polygon(body.color, &vertices, c.transform, g);
+                for (i, a) in vertices.iter().enumerate() {
+                    let b = vertices[(i + 1) % vertices.len()];
+                    line(edge, 1.0, [a[0], a[1], b[0], b[1]], c.transform, g);
+                }
+            }
+        }
     }
 }
 
-pub fn render_softbody(c: Context, g: &mut G2d, softbody: &simulation::SoftBody) {
-    for i in 0..softbody.springs.len() {
-        render_spring(c, g, softbody, i);
+pub fn render_softbody(
+    c: Context,
+    g: &mut G2d,
+    softbody: &simulation::SoftBody,
+    settings: &RenderSettings,
+) {
+    if settings.fill || settings.outline {
+        render_skin(c, g, softbody, settings);
+    }
+
+    if settings.wireframe || settings.heatmap != HeatmapMode::Off {
+        for i in 0..softbody.springs.len() {
+            render_spring(c, g, softbody, i, settings);
+        }
+        for i in 0..softbody.elements.len() {
+            render_element(c, g, softbody, i, settings);
+        }
     }
 
-    for point in &softbody.points {
-        render_point(c, g, point);
+    if settings.wireframe {
+        for point in &softbody.points {
+            render_point(c, g, point);
+        }
+    }
+}
+
+pub fn render_skin(
+    c: Context,
+    g: &mut G2d,
+    softbody: &simulation::SoftBody,
+    settings: &RenderSettings,
+) {
+    // Ear clipping can't cut holes, so bodies with holes are filled from their
+    // own mesh and left unsmoothed
+    let holed = !softbody.holes.is_empty();
+    let outline = if holed {
+        softbody.outline()
+    } else {
+        geometry::chaikin(&softbody.outline(), settings.smoothing)
+    };
+    if outline.len() < 3 {
+        return;
+    }
+
+    if settings.fill {
+        if holed {
+            for triangle in &softbody.triangles {
+                let corners = triangle.map(|i| softbody.points[i].position);
+                polygon(softbody.color, &corners, c.transform, g);
+            }
+        } else {
+            for [i, j, k] in geometry::triangulate(&outline) {
+                polygon(
+                    softbody.color,
+                    &[outline[i], outline[j], outline[k]],
+                    c.transform,
+                    g,
+                );
+            }
+        }
+    }
+
+    if settings.outline {
+        let color = [
+            softbody.color[0] * 0.5,
+            softbody.color[1] * 0.5,
+            softbody.color[2] * 0.5,
+            1.0,
+        ];
+        let holes = softbody.holes.iter().map(|hole| {
+            hole.iter()
+                .map(|&i| softbody.points[i].position)
+                .collect::<Vec<_>>()
+        });
+        for ring in std::iter::once(outline).chain(holes) {
+            for i in 0..ring.len() {
+                let a = ring[i];
+                let b = ring[(i + 1) % ring.len()];
+                line(color, 1.0, [a[0], a[1], b[0], b[1]], c.transform, g);
+            }
+        }
     }
 }
 
@@ -27,22 +284,331 @@ pub fn render_point(c: Context, g: &mut G2d, point: &simulation::Point) {
     );
 }
 
-pub fn render_spring(c: Context, g: &mut G2d, softbody: &simulation::SoftBody, spring: usize) {
-    let pos1 = softbody.points[softbody.springs[spring].point1].position;
-    let pos2 = softbody.points[softbody.springs[spring].point2].position;
+/// Draws the edges of a finite element; under the strain heatmap they take the
+/// colour of the element's strain.
+pub fn render_element(
+    c: Context,
+    g: &mut G2d,
+    softbody: &simulation::SoftBody,
+    element: usize,
+    settings: &RenderSettings,
+) {
+    let element = &softbody.elements[element];
+    let corners = element.corners.map(|i| softbody.points[i].position);
+    let color = match settings.heatmap {
+        HeatmapMode::Strain => settings
+            .heatmap
+            .color(element.strain(corners), settings.heatmap_range),
+        _ => [0.9, 0.9, 0.9, 0.6],
+    };
+    for i in 0..3 {
+        let (a, b) = (corners[i], corners[(i + 1) % 3]);
+        line(color, 0.5, [a[0], a[1], b[0], b[1]], c.transform, g);
+    }
+}
 
-    let dx = pos2[0] - pos1[0];
-    let dy = pos2[1] - pos1[1];
+pub fn render_spring(
+    c: Context,
+    g: &mut G2d,
+    softbody: &simulation::SoftBody,
+    spring: usize,
+    settings: &RenderSettings,
+) {
+    let spring = &softbody.springs[spring];
+    let pos1 = softbody.points[spring.point1].position;
+    let pos2 = softbody.points[spring.point2].position;
 
-    let distance_sq = dx * dx + dy * dy;
-    let distance = distance_sq.sqrt();
+    let value = match settings.heatmap {
+        HeatmapMode::Off => 0.0,
+        HeatmapMode::Strain => spring.strain(&softbody.points),
+        HeatmapMode::Force => spring.force(&softbody.points),
+        HeatmapMode::Plastic => spring.plastic_strain(),
+    };
+    let color = match settings.heatmap {
+        HeatmapMode::Off => [0.9, 0.9, 0.9, 0.6],
+        mode => mode.color(value, settings.heatmap_range),
+    };
 
-    let color = (softbody.springs[spring].rest_length / distance) as f32;
     line(
-        [1.0 - color, color - 1.0, color, 1.0],
+        color,
         1.0,
         [pos1[0], pos1[1], pos2[0], pos2[1]],
         c.transform,
         g,
     )
 }
+
+/// Joints between softbodies: rods and ropes as lines between their points,
+/// with a dot where points are pinned or welded together. Slack ropes are dimmer.
+pub fn render_joints(c: Context, g: &mut G2d, softbodies: &simulation::SoftBodyCollection) {
+    for joint in &softbodies.joints {
+        let position =
+            |(body, point): (usize, usize)| softbodies.softbodies[body].points[point].position;
+        let (a, b) = (position(joint.a), position(joint.b));
+        match joint.kind {
+            JointKind::Distance | JointKind::Rope => {
+                let taut = (b[0] - a[0]).hypot(b[1] - a[1]) >= joint.length - 0.5;
+                let alpha = if joint.kind == JointKind::Rope && !taut {
+                    0.4
+                } else {
+                    0.9
+                };
+                line(
+                    [0.9, 0.9, 0.9, alpha],
+                    1.5,
+                    [a[0], a[1], b[0], b[1]],
+                    c.transform,
+                    g,
+                );
+            }
+            JointKind::Pin | JointKind::Weld => {
+                let color = if joint.kind == JointKind::Weld {
+                    [0.9, 0.6, 0.2, 1.0]
+                } else {
+                    [0.9, 0.9, 0.9, 1.0]
+                };
+                ellipse(color, ellipse::circle(a[0], a[1], 3.0), c.transform, g);
+            }
+        }
+    }
+}
+
+/// Fluids as translucent water over the bodies in them, with a line along the
+/// surface.
+pub fn render_fluids(c: Context, g: &mut G2d, fluids: &[Fluid]) {
+    for fluid in fluids {
+        let volume = fluid.volume();
+        if volume.len() < 3 {
+            continue;
+        }
+        polygon([0.2, 0.45, 0.9, 0.35], &volume, c.transform, g);
+        let surface: Vec<&[f64; 2]> = volume
+            .iter()
+            .filter(|p| (p[1] - fluid.surface).abs() < 1e-6)
+            .collect();
+        if let [a, b] = surface[..] {
+            line(
+                [0.5, 0.7, 1.0, 0.8],
+                1.0,
+                [a[0], a[1], b[0], b[1]],
+                c.transform,
+                g,
+            );
+        }
+    }
+}
+
+/// Outlines the regions of the force fields; fields acting everywhere have
+/// nothing to show.
+pub fn render_fields(c: Context, g: &mut G2d, fields: &[Field]) {
+    let color = [0.6, 0.8, 1.0, 0.3];
+    for field in fields.iter().filter(|field| field.enabled) {
+        match field.region {
+            Region::Everywhere => {}
+            Region::Circle { center, radius } => {
+                Ellipse::new_border(color, 1.0).draw(
+                    ellipse::circle(center[0], center[1], radius),
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+            Region::Rect([min_x, min_y, max_x, max_y]) => {
+                Rectangle::new_border(color, 1.0).draw(
+                    [min_x, min_y, max_x - min_x, max_y - min_y],
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+        }
+    }
+}
+
+/// Shows the drag that sets the launch velocity of a body being placed.
+pub fn render_placement(c: Context, g: &mut G2d, start: [f64; 2], end: [f64; 2]) {
+    let color = [1.0, 1.0, 1.0, 0.8];
+    ellipse(
+        color,
+        ellipse::circle(start[0], start[1], 4.0),
+        c.transform,
+        g,
+    );
+    line(
+        color,
+        1.0,
+        [start[0], start[1], end[0], end[1]],
+        c.transform,
+        g,
+    );
+}
+
+/// Shows the outline being drawn, closed back to its first vertex through the cursor.
+pub fn render_outline_draft(c: Context, g: &mut G2d, outline: &[[f64; 2]], cursor: [f64; 2]) {
+    let color = [1.0, 1.0, 1.0, 0.8];
+    for (i, p) in outline.iter().enumerate() {
+        ellipse(color, ellipse::circle(p[0], p[1], 3.0), c.transform, g);
+        let next = outline.get(i + 1).unwrap_or(&cursor);
+        line(color, 1.0, [p[0], p[1], next[0], next[1]], c.transform, g);
+    }
+    if let Some(first) = outline.first() {
+        let faint = [1.0, 1.0, 1.0, 0.3];
+        line(
+            faint,
+            1.0,
+            [cursor[0], cursor[1], first[0], first[1]],
+            c.transform,
+            g,
+        );
+    }
+}
+
+/// Draws a colorbar for the active heatmap in the bottom-left corner of the window.
+pub fn render_legend(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    settings: &RenderSettings,
+    window_size: [f64; 2],
+) {
+    if settings.heatmap == HeatmapMode::Off {
+        return;
+    }
+
+    const STEPS: usize = 64;
+    const WIDTH: f64 = 200.0;
+    const HEIGHT: f64 = 12.0;
+    let x = 20.0;
+    let y = window_size[1] - 40.0;
+
+    let mode = settings.heatmap;
+    let range = settings.heatmap_range;
+    let min = if mode.diverging() { -range } else { 0.0 };
+    for i in 0..STEPS {
+        let t = (i as f64 + 0.5) / STEPS as f64;
+        rectangle(
+            mode.color(min + (range - min) * t, range),
+            [
+                x + WIDTH * i as f64 / STEPS as f64,
+                y,
+                WIDTH / STEPS as f64 + 0.5,
+                HEIGHT,
+            ],
+            c.transform,
+            g,
+        );
+    }
+
+    let text = text::Text::new_color([1.0, 1.0, 1.0, 1.0], 12);
+    let labels = [
+        (mode.label().to_string(), x, y - 6.0),
+        (format!("{:.2}", min), x, y + HEIGHT + 14.0),
+        (format!("{:.2}", range), x + WIDTH - 24.0, y + HEIGHT + 14.0),
+    ];
+    for (label, lx, ly) in labels {
+        text.draw(&label, glyphs, &c.draw_state, c.transform.trans(lx, ly), g)
+            .unwrap();
+    }
+}
+
+pub fn render_debug(
+    c: Context,
+    g: &mut G2d,
+    softbodies: &simulation::SoftBodyCollection,
+    debug: &DebugSettings,
+) {
+    // Vectors are tiny per-step quantities, so scale them up to be visible
+    const VELOCITY_SCALE: f64 = 5.0;
+    const FORCE_SCALE: f64 = 50.0;
+
+    if debug.grid {
+        let size = softbodies.grid.cell_size;
+        for (x, y) in softbodies.grid.cells() {
+            Rectangle::new_border([0.3, 0.3, 0.3, 0.6], 0.5).draw(
+                [x as f64 * size, y as f64 * size, size, size],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+
+    for softbody in &softbodies.softbodies {
+        if debug.aabbs {
+            let [min_x, min_y, max_x, max_y] = softbody.aabb();
+            Rectangle::new_border([1.0, 1.0, 0.0, 0.8], 1.0).draw(
+                [min_x, min_y, max_x - min_x, max_y - min_y],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+
+        if debug.centroids {
+            let [x, y] = softbody.centroid();
+            ellipse(
+                [1.0, 1.0, 1.0, 1.0],
+                rectangle::centered_square(x, y, 4.0),
+                c.transform,
+                g,
+            );
+        }
+
+        for point in &softbody.points {
+            let [x, y] = point.position;
+            if debug.velocities {
+                let [vx, vy] = point.velocity();
+                line(
+                    [0.0, 1.0, 0.0, 1.0],
+                    0.5,
+                    [x, y, x + vx * VELOCITY_SCALE, y + vy * VELOCITY_SCALE],
+                    c.transform,
+                    g,
+                );
+            }
+            if debug.forces {
+                let [fx, fy] = point.last_force();
+                line(
+                    [1.0, 0.5, 0.0, 1.0],
+                    0.5,
+                    [x, y, x + fx * FORCE_SCALE, y + fy * FORCE_SCALE],
+                    c.transform,
+                    g,
+                );
+            }
+        }
+    }
+
+    if debug.contacts {
+        let position = |(body, point): (usize, usize)| {
+            Some(softbodies.softbodies.get(body)?.points.get(point)?.position)
+        };
+        for contact in &softbodies.contacts {
+            let (Some(a), Some(b)) = (position(contact.a), position(contact.b)) else {
+                continue;
+            };
+            line(
+                [1.0, 0.0, 1.0, 1.0],
+                1.0,
+                [a[0], a[1], b[0], b[1]],
+                c.transform,
+                g,
+            );
+
+            let mid = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+            let length = 5.0 + contact.depth * 2.0;
+            line(
+                [0.0, 1.0, 1.0, 1.0],
+                1.0,
+                [
+                    mid[0],
+                    mid[1],
+                    mid[0] + contact.normal[0] * length,
+                    mid[1] + contact.normal[1] * length,
+                ],
+                c.transform,
+                g,
+            );
+        }
+    }
+}
@@ -0,0 +1,936 @@
+use std::f64::consts::PI;
+
+use crate::collider::Collider;
+use crate::geometry;
+
+// mass per unit area, about what a spring body of the same size weighs
+pub const DEFAULT_DENSITY: f64 = 0.0025;
+const DEFAULT_RESTITUTION: f64 = 0.2;
+// Coulomb friction coefficient at contacts
+const DEFAULT_FRICTION: f64 = 0.5;
+// velocity kept per full step, like a softbody point's friction
+const AIR_FRICTION: f64 = 0.999;
+const DEFAULT_COLOR: [f32; 4] = [0.6, 0.45, 0.3, 1.0];
+// passes over the contacts each step; more settles stacks faster
+const SOLVER_ITERATIONS: usize = 10;
+// how far a contact may move between steps and still count as the same one,
+// starting from the impulse it needed last time
+const WARM_START_RADIUS: f64 = 2.0;
+// approach speed below which contacts don't bounce, so resting bodies stay put
+const BOUNCE_THRESHOLD: f64 = 0.5;
+// overlap left alone, and the share of the rest removed per step
+const SLOP: f64 = 0.5;
+const CORRECTION: f64 = 0.6;
+// sides of the polygon standing in for a circle where an outline is needed
+const CIRCLE_SEGMENTS: usize = 24;
+
+#[derive(Clone, PartialEq)]
+pub enum RigidShape {
+    Circle(f64),
+    // convex, around the centre of mass
+    Polygon(Vec<[f64; 2]>),
+}
+
+/// A body that moves and turns but never deforms.
+#[derive(Clone, PartialEq)]
+pub struct RigidBody {
+    pub shape: RigidShape,
+    // centre of mass
+    pub position: [f64; 2],
+    pub angle: f64,
+    pub velocity: [f64; 2],
+    pub angular_velocity: f64,
+    // inverses, so a body that can't move is just zero here
+    inverse_mass: f64,
+    inverse_inertia: f64,
+    pub restitution: f64,
+    pub friction: f64,
+    pub color: [f32; 4],
+    // multiplier on the world's gravity for this body; negative floats up
+    pub gravity_scale: f64,
+}
+
+fn cross(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn dot(a: [f64; 2], b: [f64; 2]) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn rotate(v: [f64; 2], angle: f64) -> [f64; 2] {
+    let (sin, cos) = angle.sin_cos();
+    [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos]
+}
+
+/// Outward unit normals of a convex polygon's edges, edge `i` running from
+/// vertex `i` to the next.
+fn edge_normals(polygon: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let n = polygon.len();
+    let side = geometry::signed_area(polygon).signum();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let len = dx.hypot(dy).max(f64::EPSILON);
+            [side * dy / len, -side * dx / len]
+        })
+        .collect()
+}
+
+impl RigidBody {
+    fn new(shape: RigidShape, position: [f64; 2], area: f64, inertia_per_density: f64) -> Self {
+        RigidBody {
+            shape,
+            position,
+            angle: 0.0,
+            velocity: [0.0, 0.0],
+            angular_velocity: 0.0,
+            inverse_mass: 1.0 / (DEFAULT_DENSITY * area),
+            inverse_inertia: 1.0 / (DEFAULT_DENSITY * inertia_per_density),
+            restitution: DEFAULT_RESTITUTION,
+            friction: DEFAULT_FRICTION,
+            color: DEFAULT_COLOR,
+            gravity_scale: 1.0,
+        }
+    }
+
+    pub fn new_circle(center: [f64; 2], radius: f64) -> Self {
+        let radius = radius.max(1.0);
+        let area = PI * radius * radius;
+        RigidBody::new(
+            RigidShape::Circle(radius),
+            center,
+            area,
+            area * radius * radius / 2.0,
+        )
+    }
+
+    /// The convex hull of `outline`, placed where the outline is. Contacts
+    /// only handle convex shapes, so a concave outline is filled out.
+    pub fn new_polygon(outline: &[[f64; 2]]) -> Self {
+        let hull: Vec<[f64; 2]> = geometry::convex_hull(outline)
+            .into_iter()
+            .map(|i| outline[i])
+            .collect();
+
+        // Sum over the triangles fanned out from the origin
+        let (mut area, mut moment, mut centroid) = (0.0, 0.0, [0.0, 0.0]);
+        for i in 0..hull.len() {
+            let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+            let twice = cross(a, b);
+            area += twice / 2.0;
+            centroid[0] += twice * (a[0] + b[0]) / 6.0;
+            centroid[1] += twice * (a[1] + b[1]) / 6.0;
+            moment += twice * (dot(a, a) + dot(a, b) + dot(b, b)) / 12.0;
+        }
+        if area.abs() < 1.0 {
+            // Too thin to have a sensible inertia; treat it as a small disc
+            let center = hull.first().copied().unwrap_or([0.0, 0.0]);
+            return RigidBody::new_circle(center, 1.0);
+        }
+        let centroid = [centroid[0] / area, centroid[1] / area];
+        // Moment about the origin, moved to the centroid
+        let inertia = moment.abs() - area.abs() * dot(centroid, centroid);
+
+        let vertices = hull
+            .iter()
+            .map(|p| [p[0] - centroid[0], p[1] - centroid[1]])
+            .collect();
+        RigidBody::new(RigidShape::Polygon(vertices), centroid, area.abs(), inertia)
+    }
+
+    pub fn new_box(center: [f64; 2], width: f64, height: f64) -> Self {
+        let (w, h) = (width / 2.0, height / 2.0);
+        RigidBody::new_polygon(&[
+            [center[0] - w, center[1] - h],
+            [center[0] + w, center[1] - h],
+            [center[0] + w, center[1] + h],
+            [center[0] - w, center[1] + h],
+        ])
+    }
+
+    pub fn new_regular_polygon(center: [f64; 2], radius: f64, sides: usize) -> Self {
+        let sides = sides.max(3);
+        let outline: Vec<[f64; 2]> = (0..sides)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / sides as f64;
+                [
+                    center[0] + radius * angle.cos(),
+                    center[1] + radius * angle.sin(),
+                ]
+            })
+            .collect();
+        RigidBody::new_polygon(&outline)
+    }
+
+    /// Stands in for the edges of the world and the colliders, which push but
+    /// never move.
+    fn ground() -> Self {
+        RigidBody::new_circle([0.0, 0.0], 1.0).with_static()
+    }
+
+    /// Fixes the body in place, as if its mass were infinite: it pushes what
+    /// touches it but is never moved, which makes it a pivot or a ledge that
+    /// can be placed and removed while the simulation runs.
+    pub fn with_static(mut self) -> Self {
+        self.inverse_mass = 0.0;
+        self.inverse_inertia = 0.0;
+        self.velocity = [0.0, 0.0];
+        self.angular_velocity = 0.0;
+        self
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.inverse_mass == 0.0
+    }
+
+    pub fn with_density(mut self, density: f64) -> Self {
+        if self.is_static() {
+            return self;
+        }
+        let current = self.mass() / self.area();
+        let scale = current / density.max(f64::EPSILON);
+        self.inverse_mass *= scale;
+        self.inverse_inertia *= scale;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: [f64; 2]) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, scale: f64) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    pub fn mass(&self) -> f64 {
+        if self.inverse_mass == 0.0 {
+            f64::INFINITY
+        } else {
+            1.0 / self.inverse_mass
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        match &self.shape {
+            RigidShape::Circle(radius) => PI * radius * radius,
+            RigidShape::Polygon(vertices) => geometry::signed_area(vertices).abs(),
+        }
+    }
+
+    /// Corners in world coordinates; empty for a circle.
+    pub fn vertices(&self) -> Vec<[f64; 2]> {
+        match &self.shape {
+            RigidShape::Circle(_) => Vec::new(),
+            RigidShape::Polygon(vertices) => vertices
+                .iter()
+                .map(|&v| {
+                    let r = rotate(v, self.angle);
+                    [self.position[0] + r[0], self.position[1] + r[1]]
+                })
+                .collect(),
+        }
+    }
+
+    /// The outline in world coordinates, with a circle as a many-sided polygon.
+    pub fn outline(&self) -> Vec<[f64; 2]> {
+        match &self.shape {
+            RigidShape::Circle(radius) => (0..CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f64 / CIRCLE_SEGMENTS as f64 * 2.0 * PI;
+                    [
+                        self.position[0] + radius * angle.cos(),
+                        self.position[1] + radius * angle.sin(),
+                    ]
+                })
+                .collect(),
+            RigidShape::Polygon(_) => self.vertices(),
+        }
+    }
+
+    /// [min_x, min_y, max_x, max_y]
+    pub fn aabb(&self) -> [f64; 4] {
+        match &self.shape {
+            RigidShape::Circle(radius) => [
+                self.position[0] - radius,
+                self.position[1] - radius,
+                self.position[0] + radius,
+                self.position[1] + radius,
+            ],
+            RigidShape::Polygon(_) => self.vertices().iter().fold(
+                [f64::MAX, f64::MAX, f64::MIN, f64::MIN],
+                |[min_x, min_y, max_x, max_y], p| {
+                    [
+                        min_x.min(p[0]),
+                        min_y.min(p[1]),
+                        max_x.max(p[0]),
+                        max_y.max(p[1]),
+                    ]
+                },
+            ),
+        }
+    }
+
+    /// Velocity of the material of the body at world position `p`.
+    pub fn velocity_at(&self, p: [f64; 2]) -> [f64; 2] {
+        let r = [p[0] - self.position[0], p[1] - self.position[1]];
+        [
+            self.velocity[0] - self.angular_velocity * r[1],
+            self.velocity[1] + self.angular_velocity * r[0],
+        ]
+    }
+
+    pub fn apply_impulse(&mut self, impulse: [f64; 2], at: [f64; 2]) {
+        let r = [at[0] - self.position[0], at[1] - self.position[1]];
+        self.velocity[0] += impulse[0] * self.inverse_mass;
+        self.velocity[1] += impulse[1] * self.inverse_mass;
+        self.angular_velocity += cross(r, impulse) * self.inverse_inertia;
+    }
+
+    /// Moves the body as an impulse at `at` would, but straight to the new
+    /// position rather than through its velocity.
+    fn shift(&mut self, push: [f64; 2], at: [f64; 2]) {
+        let r = [at[0] - self.position[0], at[1] - self.position[1]];
+        self.position[0] += push[0] * self.inverse_mass;
+        self.position[1] += push[1] * self.inverse_mass;
+        self.angle += cross(r, push) * self.inverse_inertia;
+    }
+
+    /// Resistance to an impulse along `normal` at `at`, as the inverse of the
+    /// effective mass there.
+    fn inverse_mass_at(&self, at: [f64; 2], normal: [f64; 2]) -> f64 {
+        let r = [at[0] - self.position[0], at[1] - self.position[1]];
+        self.inverse_mass + cross(r, normal).powi(2) * self.inverse_inertia
+    }
+
+    /// If `p` is inside, the outward normal of the nearest surface and how far
+    /// below it `p` is.
+    pub fn surface_contact(&self, p: [f64; 2]) -> Option<([f64; 2], f64)> {
+        match &self.shape {
+            RigidShape::Circle(radius) => {
+                let d = [p[0] - self.position[0], p[1] - self.position[1]];
+                let distance = d[0].hypot(d[1]);
+                if distance >= *radius {
+                    return None;
+                }
+                let normal = if distance > 0.0 {
+                    [d[0] / distance, d[1] / distance]
+                } else {
+                    [0.0, -1.0]
+                };
+                Some((normal, radius - distance))
+            }
+            RigidShape::Polygon(_) => {
+                let vertices = self.vertices();
+                let normals = edge_normals(&vertices);
+                // The face p is least far behind is the one to leave through
+                let (normal, distance) = vertices.iter().zip(&normals).fold(
+                    ([0.0, -1.0], f64::MIN),
+                    |best, (&a, &normal)| {
+                        let distance = dot([p[0] - a[0], p[1] - a[1]], normal);
+                        if distance > best.1 {
+                            (normal, distance)
+                        } else {
+                            best
+                        }
+                    },
+                );
+                (distance < 0.0).then_some((normal, -distance))
+            }
+        }
+    }
+
+    pub fn contains(&self, p: [f64; 2]) -> bool {
+        self.surface_contact(p).is_some()
+    }
+
+    fn integrate(&mut self, gravity: [f64; 2], dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        let friction = AIR_FRICTION.powf(dt);
+        self.velocity = [
+            (self.velocity[0] + gravity[0] * self.gravity_scale * dt) * friction,
+            (self.velocity[1] + gravity[1] * self.gravity_scale * dt) * friction,
+        ];
+        self.angular_velocity *= friction;
+    }
+
+    fn advance(&mut self, dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        self.position[0] += self.velocity[0] * dt;
+        self.position[1] += self.velocity[1] * dt;
+        self.angle += self.angular_velocity * dt;
+    }
+
+    /// Pushes a softbody point at `position` out of the body, trading an
+    /// impulse with it as if it were a particle of `mass`.
+    pub fn collide_point(&mut self, position: &mut [f64; 2], velocity: &mut [f64; 2], mass: f64) {
+        let Some((normal, depth)) = self.surface_contact(*position) else {
+            return;
+        };
+        let point_weight = 1.0 / mass;
+
+        // Split the overlap by how easily each side moves
+        let share = point_weight / (point_weight + self.inverse_mass);
+        for axis in 0..2 {
+            position[axis] += normal[axis] * depth * share;
+            self.position[axis] -= normal[axis] * depth * (1.0 - share);
+        }
+
+        let body_velocity = self.velocity_at(*position);
+        let relative = [
+            velocity[0] - body_velocity[0],
+            velocity[1] - body_velocity[1],
+        ];
+        let approach = dot(relative, normal);
+        if approach >= 0.0 {
+            return;
+        }
+        let impulse = -(1.0 + self.restitution) * approach
+            / (point_weight + self.inverse_mass_at(*position, normal));
+
+        // Friction takes out sliding, up to what the normal impulse allows
+        let tangent = [-normal[1], normal[0]];
+        let sliding =
+            -dot(relative, tangent) / (point_weight + self.inverse_mass_at(*position, tangent));
+        let sliding = sliding.clamp(-self.friction * impulse, self.friction * impulse);
+
+        let total = [
+            normal[0] * impulse + tangent[0] * sliding,
+            normal[1] * impulse + tangent[1] * sliding,
+        ];
+        velocity[0] += total[0] * point_weight;
+        velocity[1] += total[1] * point_weight;
+        self.apply_impulse([-total[0], -total[1]], *position);
+    }
+}
+
+/// A point where two bodies touch, kept between steps so the solver can start
+/// from the impulses it found last time. `a` is `None` when `b` touches the
+/// static world, and the normal points from `a` to `b`.
+#[derive(Clone)]
+pub struct Contact {
+    a: Option<usize>,
+    b: usize,
+    point: [f64; 2],
+    normal: [f64; 2],
+    depth: f64,
+    // separating speed the contact aims for, from restitution
+    bounce: f64,
+    // impulses accumulated over the solver passes
+    normal_impulse: f64,
+    tangent_impulse: f64,
+}
+
+/// Where `circle` (at `center`) overlaps `polygon`, with normals from the
+/// polygon to the circle.
+fn circle_polygon(
+    center: [f64; 2],
+    radius: f64,
+    polygon: &RigidBody,
+) -> Option<([f64; 2], [f64; 2], f64)> {
+    if let Some((normal, depth)) = polygon.surface_contact(center) {
+        let point = [
+            center[0] - normal[0] * radius,
+            center[1] - normal[1] * radius,
+        ];
+        return Some((point, normal, depth + radius));
+    }
+    let vertices = polygon.vertices();
+    let n = vertices.len();
+    let closest = (0..n)
+        .map(|i| geometry::closest_on_segment(center, vertices[i], vertices[(i + 1) % n]))
+        .min_by(|a, b| {
+            (a[0] - center[0])
+                .hypot(a[1] - center[1])
+                .total_cmp(&(b[0] - center[0]).hypot(b[1] - center[1]))
+        })?;
+    let d = [center[0] - closest[0], center[1] - closest[1]];
+    let distance = d[0].hypot(d[1]);
+    (distance < radius && distance > 0.0).then(|| {
+        (
+            closest,
+            [d[0] / distance, d[1] / distance],
+            radius - distance,
+        )
+    })
+}
+
+/// The face of `reference` that `other` sinks least far behind, and how far
+/// behind it `other`'s deepest vertex is. Positive means the polygons are apart.
+fn max_separation(
+    reference: &[[f64; 2]],
+    normals: &[[f64; 2]],
+    other: &[[f64; 2]],
+) -> (usize, f64) {
+    (0..reference.len())
+        .map(|i| {
+            let a = reference[i];
+            let separation = other
+                .iter()
+                .map(|v| dot([v[0] - a[0], v[1] - a[1]], normals[i]))
+                .fold(f64::INFINITY, f64::min);
+            (i, separation)
+        })
+        .fold(
+            (0, f64::MIN),
+            |best, face| if face.1 > best.1 { face } else { best },
+        )
+}
+
+/// Cuts the segment down to the part where `dot(normal, p) <= offset`.
+fn clip(segment: [[f64; 2]; 2], normal: [f64; 2], offset: f64) -> Option<[[f64; 2]; 2]> {
+    let d = segment.map(|p| dot(normal, p) - offset);
+    match (d[0] <= 0.0, d[1] <= 0.0) {
+        (true, true) => Some(segment),
+        (false, false) => None,
+        (inside0, _) => {
+            let t = d[0] / (d[0] - d[1]);
+            let cut = [
+                segment[0][0] + (segment[1][0] - segment[0][0]) * t,
+                segment[0][1] + (segment[1][1] - segment[0][1]) * t,
+            ];
+            Some(if inside0 {
+                [segment[0], cut]
+            } else {
+                [cut, segment[1]]
+            })
+        }
+    }
+}
+
+/// Contacts between two convex polygons by the separating axis test: the
+/// shallowest face of either is the reference, and the other polygon's face
+/// most opposed to it is clipped to its sides.
+fn polygon_contacts(a: &[[f64; 2]], b: &[[f64; 2]]) -> Vec<([f64; 2], [f64; 2], f64)> {
+    let (normals_a, normals_b) = (edge_normals(a), edge_normals(b));
+    let (face_a, separation_a) = max_separation(a, &normals_a, b);
+    if separation_a > 0.0 {
+        return Vec::new();
+    }
+    let (face_b, separation_b) = max_separation(b, &normals_b, a);
+    if separation_b > 0.0 {
+        return Vec::new();
+    }
+
+    // Lean toward `a` so near-ties don't flip between frames
+    let flip = separation_b > 0.98 * separation_a + 0.001;
+    let (reference, incident, incident_normals, face) = if flip {
+        (b, a, &normals_a, face_b)
+    } else {
+        (a, b, &normals_b, face_a)
+    };
+    let normal = if flip {
+        normals_b[face]
+    } else {
+        normals_a[face]
+    };
+
+    let opposed = (0..incident.len())
+        .min_by(|&i, &j| {
+            dot(incident_normals[i], normal).total_cmp(&dot(incident_normals[j], normal))
+        })
+        .unwrap_or(0);
+    let segment = [incident[opposed], incident[(opposed + 1) % incident.len()]];
+
+    let r1 = reference[face];
+    let r2 = reference[(face + 1) % reference.len()];
+    let length = (r2[0] - r1[0]).hypot(r2[1] - r1[1]).max(f64::EPSILON);
+    let tangent = [(r2[0] - r1[0]) / length, (r2[1] - r1[1]) / length];
+    let Some(segment) = clip(segment, [-tangent[0], -tangent[1]], -dot(tangent, r1))
+        .and_then(|segment| clip(segment, tangent, dot(tangent, r2)))
+    else {
+        return Vec::new();
+    };
+
+    let direction = if flip { -1.0 } else { 1.0 };
+    segment
+        .iter()
+        .filter_map(|&p| {
+            let separation = dot([p[0] - r1[0], p[1] - r1[1]], normal);
+            (separation <= 0.0).then_some((
+                p,
+                [normal[0] * direction, normal[1] * direction],
+                -separation,
+            ))
+        })
+        .collect()
+}
+
+/// Points where `a` and `b` overlap, as (point, normal from `a` to `b`, depth).
+fn body_contacts(a: &RigidBody, b: &RigidBody) -> Vec<([f64; 2], [f64; 2], f64)> {
+    match (&a.shape, &b.shape) {
+        (RigidShape::Circle(ra), RigidShape::Circle(rb)) => {
+            let d = [b.position[0] - a.position[0], b.position[1] - a.position[1]];
+            let distance = d[0].hypot(d[1]);
+            if distance >= ra + rb {
+                return Vec::new();
+            }
+            let normal = if distance > 0.0 {
+                [d[0] / distance, d[1] / distance]
+            } else {
+                [0.0, -1.0]
+            };
+            let point = [
+                a.position[0] + normal[0] * ra,
+                a.position[1] + normal[1] * ra,
+            ];
+            vec![(point, normal, ra + rb - distance)]
+        }
+        (RigidShape::Polygon(_), RigidShape::Circle(radius)) => {
+            circle_polygon(b.position, *radius, a).into_iter().collect()
+        }
+        (RigidShape::Circle(radius), RigidShape::Polygon(_)) => {
+            circle_polygon(a.position, *radius, b)
+                .map(|(point, normal, depth)| (point, [-normal[0], -normal[1]], depth))
+                .into_iter()
+                .collect()
+        }
+        (RigidShape::Polygon(_), RigidShape::Polygon(_)) => {
+            polygon_contacts(&a.vertices(), &b.vertices())
+        }
+    }
+}
+
+/// Points where `body` pokes out of the world or into a collider, with
+/// normals pointing back into the open.
+fn static_contacts(
+    body: &RigidBody,
+    colliders: &[Collider],
+    bounds: [f64; 2],
+) -> Vec<([f64; 2], [f64; 2], f64)> {
+    let mut contacts = Vec::new();
+    let extremes = match &body.shape {
+        RigidShape::Circle(radius) => [[-1.0, 0.0], [1.0, 0.0], [0.0, -1.0], [0.0, 1.0]]
+            .iter()
+            .map(|d| {
+                [
+                    body.position[0] + d[0] * radius,
+                    body.position[1] + d[1] * radius,
+                ]
+            })
+            .collect(),
+        RigidShape::Polygon(_) => body.vertices(),
+    };
+    for p in extremes {
+        for axis in 0..2 {
+            let mut normal = [0.0, 0.0];
+            if p[axis] < 0.0 {
+                normal[axis] = 1.0;
+                contacts.push((p, normal, -p[axis]));
+            } else if p[axis] > bounds[axis] {
+                normal[axis] = -1.0;
+                contacts.push((p, normal, p[axis] - bounds[axis]));
+            }
+        }
+    }
+
+    let aabb = body.aabb();
+    for collider in colliders.iter().filter(|c| c.overlaps(aabb)) {
+        match &body.shape {
+            RigidShape::Circle(radius) => {
+                let (surface, normal) = collider.closest(body.position);
+                let d = [body.position[0] - surface[0], body.position[1] - surface[1]];
+                let distance = d[0].hypot(d[1]);
+                if geometry::contains(&collider.outline, body.position) {
+                    contacts.push((surface, normal, radius + distance));
+                } else if distance < *radius && distance > 0.0 {
+                    contacts.push((
+                        surface,
+                        [d[0] / distance, d[1] / distance],
+                        radius - distance,
+                    ));
+                }
+            }
+            RigidShape::Polygon(_) => {
+                for v in body.vertices() {
+                    if let Some((surface, normal)) = collider.push_out(v) {
+                        let depth = (v[0] - surface[0]).hypot(v[1] - surface[1]);
+                        contacts.push((v, normal, depth));
+                    }
+                }
+                for &v in &collider.outline {
+                    if let Some((normal, depth)) = body.surface_contact(v) {
+                        contacts.push((v, [-normal[0], -normal[1]], depth));
+                    }
+                }
+            }
+        }
+    }
+    contacts
+}
+
+/// Mutable access to two different bodies, standing in a fixed body for `None`.
+fn pair<'a>(
+    bodies: &'a mut [RigidBody],
+    ground: &'a mut RigidBody,
+    a: Option<usize>,
+    b: usize,
+) -> (&'a mut RigidBody, &'a mut RigidBody) {
+    match a {
+        None => (ground, &mut bodies[b]),
+        Some(a) => {
+            let (left, right) = bodies.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        }
+    }
+}
+
+/// Advances the rigid bodies by `dt`, resolving contacts between them and
+/// with the edges of the world and the colliders.
+pub fn step(
+    bodies: &mut [RigidBody],
+    contacts: &mut Vec<Contact>,
+    colliders: &[Collider],
+    bounds: [f64; 2],
+    gravity: [f64; 2],
+    dt: f64,
+) {
+    for body in bodies.iter_mut() {
+        body.integrate(gravity, dt);
+    }
+
+    let previous = std::mem::take(contacts);
+    let mut found = Vec::new();
+    // Static bodies push but can't be pushed, so there's nothing to solve
+    // between them and the world or each other
+    for b in 0..bodies.len() {
+        if !bodies[b].is_static() {
+            for (point, normal, depth) in static_contacts(&bodies[b], colliders, bounds) {
+                found.push((None, b, point, normal, depth));
+            }
+        }
+        for a in 0..b {
+            if bodies[a].is_static() && bodies[b].is_static() {
+                continue;
+            }
+            for (point, normal, depth) in body_contacts(&bodies[a], &bodies[b]) {
+                found.push((Some(a), b, point, normal, depth));
+            }
+        }
+    }
+
+    let mut ground = RigidBody::ground();
+    *contacts = found
+        .into_iter()
+        .map(|(a, b, point, normal, depth)| {
+            let (body_a, body_b) = pair(bodies, &mut ground, a, b);
+            let (va, vb) = (body_a.velocity_at(point), body_b.velocity_at(point));
+            let approach = dot([vb[0] - va[0], vb[1] - va[1]], normal);
+            let restitution = body_a.restitution.max(body_b.restitution);
+            Contact {
+                a,
+                b,
+                point,
+                normal,
+                depth,
+                bounce: if approach < -BOUNCE_THRESHOLD {
+                    -restitution * approach
+                } else {
+                    0.0
+                },
+                normal_impulse: 0.0,
+                tangent_impulse: 0.0,
+            }
+        })
+        .collect();
+
+    // Resting contacts need about the same impulse every step, so applying
+    // last step's up front leaves the passes only the difference to find
+    for contact in contacts.iter_mut() {
+        let Some(old) = previous.iter().find(|old| {
+            old.a == contact.a
+                && old.b == contact.b
+                && (old.point[0] - contact.point[0]).hypot(old.point[1] - contact.point[1])
+                    < WARM_START_RADIUS
+        }) else {
+            continue;
+        };
+        let (a, b) = pair(bodies, &mut ground, contact.a, contact.b);
+        let (normal, point) = (contact.normal, contact.point);
+        let tangent = [-normal[1], normal[0]];
+        contact.normal_impulse = old.normal_impulse;
+        contact.tangent_impulse = old.tangent_impulse;
+        let impulse = [
+            normal[0] * old.normal_impulse + tangent[0] * old.tangent_impulse,
+            normal[1] * old.normal_impulse + tangent[1] * old.tangent_impulse,
+        ];
+        a.apply_impulse([-impulse[0], -impulse[1]], point);
+        b.apply_impulse(impulse, point);
+    }
+
+    // Sequential impulses: each pass nudges every contact toward its target
+    // speed, keeping the total impulse pushing the bodies apart. Going back
+    // and forth keeps the first contact from taking more than its share.
+    for _ in 0..SOLVER_ITERATIONS {
+        contacts.reverse();
+        for contact in contacts.iter_mut() {
+            let (a, b) = pair(bodies, &mut ground, contact.a, contact.b);
+            let (point, normal) = (contact.point, contact.normal);
+            let tangent = [-normal[1], normal[0]];
+            let (va, vb) = (a.velocity_at(point), b.velocity_at(point));
+            let relative = [vb[0] - va[0], vb[1] - va[1]];
+
+            let weight = a.inverse_mass_at(point, normal) + b.inverse_mass_at(point, normal);
+            let change = (contact.bounce - dot(relative, normal)) / weight;
+            let total = (contact.normal_impulse + change).max(0.0);
+            let change = total - contact.normal_impulse;
+            contact.normal_impulse = total;
+            a.apply_impulse([-normal[0] * change, -normal[1] * change], point);
+            b.apply_impulse([normal[0] * change, normal[1] * change], point);
+
+            let (va, vb) = (a.velocity_at(point), b.velocity_at(point));
+            let relative = [vb[0] - va[0], vb[1] - va[1]];
+            let weight = a.inverse_mass_at(point, tangent) + b.inverse_mass_at(point, tangent);
+            let limit = (a.friction * b.friction).sqrt() * contact.normal_impulse;
+            let change = -dot(relative, tangent) / weight;
+            let total = (contact.tangent_impulse + change).clamp(-limit, limit);
+            let change = total - contact.tangent_impulse;
+            contact.tangent_impulse = total;
+            a.apply_impulse([-tangent[0] * change, -tangent[1] * change], point);
+            b.apply_impulse([tangent[0] * change, tangent[1] * change], point);
+        }
+    }
+
+    for body in bodies.iter_mut() {
+        body.advance(dt);
+    }
+
+    // Velocities only stop things sinking further; push out what already overlaps
+    for contact in contacts.iter() {
+        let (a, b) = pair(bodies, &mut ground, contact.a, contact.b);
+        let (point, normal) = (contact.point, contact.normal);
+        let weight = a.inverse_mass_at(point, normal) + b.inverse_mass_at(point, normal);
+        if weight == 0.0 {
+            continue;
+        }
+        let push = (contact.depth - SLOP).max(0.0) * CORRECTION / weight;
+        a.shift([-normal[0] * push, -normal[1] * push], point);
+        b.shift([normal[0] * push, normal[1] * push], point);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_boxes_touch_along_the_shared_face() {
+        let a = RigidBody::new_box([5.0, 5.0], 10.0, 10.0);
+        let b = RigidBody::new_box([13.0, 5.0], 10.0, 10.0);
+        let contacts = polygon_contacts(&a.vertices(), &b.vertices());
+        assert_eq!(contacts.len(), 2);
+        for (point, normal, depth) in contacts {
+            assert!((normal[0] - 1.0).abs() < 1e-9 && normal[1].abs() < 1e-9);
+            assert!((depth - 2.0).abs() < 1e-9);
+            assert!((8.0..=10.0).contains(&point[0]));
+        }
+
+        let apart = RigidBody::new_box([20.0, 5.0], 10.0, 10.0);
+        assert!(polygon_contacts(&a.vertices(), &apart.vertices()).is_empty());
+    }
+
+    #[test]
+    fn concave_outlines_become_their_hull() {
+        let l_shape = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 4.0],
+            [4.0, 4.0],
+            [4.0, 10.0],
+            [0.0, 10.0],
+        ];
+        let body = RigidBody::new_polygon(&l_shape);
+        assert_eq!(body.vertices().len(), 5);
+        // The hull cuts the notch off along the diagonal from (10, 4) to (4, 10)
+        assert!((body.area() - (100.0 - 18.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_dropped_box_comes_to_rest_on_the_floor() {
+        let mut bodies = vec![RigidBody::new_box([100.0, 50.0], 40.0, 20.0)];
+        let mut contacts = Vec::new();
+        for _ in 0..600 {
+            step(
+                &mut bodies,
+                &mut contacts,
+                &[],
+                [200.0, 200.0],
+                [0.0, 0.1],
+                1.0,
+            );
+        }
+        let body = &bodies[0];
+        assert!(
+            (body.position[1] - 190.0).abs() < 1.0,
+            "{:?}",
+            body.position
+        );
+        assert!(body.velocity[0].hypot(body.velocity[1]) < 0.05);
+    }
+
+    #[test]
+    fn a_plank_balances_on_a_fixed_pivot() {
+        // A wedge with its point at (100, 180), fixed in mid-air
+        let pivot = RigidBody::new_polygon(&[[100.0, 180.0], [115.0, 200.0], [85.0, 200.0]])
+            .with_static()
+            .with_density(1.0);
+        let settle = |offset: f64| {
+            let plank = RigidBody::new_box([100.0 + offset, 175.0], 120.0, 10.0);
+            let mut bodies = vec![pivot.clone(), plank];
+            let mut contacts = Vec::new();
+            for _ in 0..200 {
+                step(
+                    &mut bodies,
+                    &mut contacts,
+                    &[],
+                    [200.0, 400.0],
+                    [0.0, 0.1],
+                    1.0,
+                );
+            }
+            assert!(bodies[0] == pivot, "the pivot moved");
+            bodies.remove(1)
+        };
+
+        // Centred, it rests on the point without tipping
+        let plank = settle(0.0);
+        assert!(plank.angle.abs() < 1e-6, "{}", plank.angle);
+        assert!(
+            (plank.position[1] - 175.0).abs() < 1.0,
+            "{:?}",
+            plank.position
+        );
+
+        // Off centre, the longer side swings down
+        assert!(settle(15.0).angle > 0.1);
+        assert!(settle(-15.0).angle < -0.1);
+    }
+
+    #[test]
+    fn softbody_points_are_pushed_out_and_trade_momentum() {
+        let mut body = RigidBody::new_box([0.0, 0.0], 20.0, 20.0);
+        let mut position = [0.0, -8.0];
+        let mut velocity = [0.0, 1.0];
+        let momentum = body.mass() * body.velocity[1] + velocity[1];
+        body.collide_point(&mut position, &mut velocity, 1.0);
+
+        assert!(!body.contains(position));
+        // No longer closing on the body
+        assert!(velocity[1] <= body.velocity[1] + 1e-9);
+        assert!(body.velocity[1] > 0.0);
+        let after = body.mass() * body.velocity[1] + velocity[1];
+        assert!((after - momentum).abs() < 1e-9);
+    }
+}
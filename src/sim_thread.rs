@@ -0,0 +1,411 @@
+use std::mem;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::command::{SimCommand, SimParam};
+use crate::simulation::{self, SoftBodyCollection};
+use crate::snapshot::{self, SnapshotReader, SnapshotWriter};
+
+const STEP_INTERVAL: Duration = Duration::from_millis(16); // ~60 FPS
+
+pub const MIN_TIME_SCALE: f64 = 0.1;
+pub const MAX_TIME_SCALE: f64 = 4.0;
+
+// spring pulling a dragged point toward the cursor
+pub const DEFAULT_DRAG_STIFFNESS: f64 = 0.2;
+pub const DEFAULT_DRAG_DAMPING: f64 = 0.3;
+
+struct Drag {
+    body: usize,
+    point: usize,
+    target: [f64; 2],
+    // where the target was at the previous step, used to estimate cursor velocity
+    last_target: [f64; 2],
+}
+
+pub struct Simulator {
+    pub softbodies: SoftBodyCollection,
+    scene: SoftBodyCollection,
+    bounds: [f64; 2],
+    time_scale: f64,
+    drag_stiffness: f64,
+    drag_damping: f64,
+    paused: bool,
+    pending_steps: u32,
+    drag: Option<Drag>,
+    // latest value of each param that lives in the world, replayed after a reset
+    // so the scene comes back with the settings the UI is showing
+    world_params: Vec<SimParam>,
+}
+
+impl Simulator {
+    pub fn new(scene: SoftBodyCollection, bounds: [f64; 2]) -> Self {
+        Simulator {
+            softbodies: scene.clone(),
+            scene,
+            bounds,
+            time_scale: 1.0,
+            drag_stiffness: DEFAULT_DRAG_STIFFNESS,
+            drag_damping: DEFAULT_DRAG_DAMPING,
+            paused: false,
+            pending_steps: 0,
+            drag: None,
+            world_params: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.paused || self.pending_steps > 0
+    }
+
+    pub fn handle(&mut self, command: SimCommand) {
+        match command {
+            SimCommand::Spawn(softbody) => self.softbodies.add(softbody),
+            SimCommand::SpawnRigid(body) => self.softbodies.add_rigid(body),
+            SimCommand::Remove(body) => {
+                if body < self.softbodies.softbodies.len() {
+                    self.softbodies.remove(body);
+                    // A drag on another body just follows it to its new index
+                    self.drag = self.drag.take().and_then(|drag| {
+                        Some(Drag {
+                            body: simulation::renumber(drag.body, body)?,
+                            ..drag
+                        })
+                    });
+                }
+            }
+            SimCommand::AddJoint(joint) => {
+                let bodies = &self.softbodies.softbodies;
+                let exists = |(body, point): (usize, usize)| {
+                    bodies.get(body).is_some_and(|sb| point < sb.points.len())
+                };
+                if exists(joint.a) && exists(joint.b) {
+                    self.softbodies.add_joint(joint);
+                }
+            }
+            SimCommand::AddField(field) => self.softbodies.add_field(field),
+            SimCommand::SetFieldEnabled(index, enabled) => {
+                if let Some(field) = self.softbodies.fields.get_mut(index) {
+                    field.enabled = enabled;
+                }
+            }
+            SimCommand::ClearFields => self.softbodies.fields.clear(),
+            SimCommand::AddFluid(fluid) => self.softbodies.add_fluid(fluid),
+            SimCommand::ClearFluids => self.softbodies.fluids.clear(),
+            SimCommand::RemoveRigid(body) => {
+                if body < self.softbodies.rigid_bodies.len() {
+                    self.softbodies.remove_rigid(body);
+                }
+            }
+            SimCommand::Drag {
+                body,
+                point,
+                target,
+            } => {
+                // Keep the old target when the same point is being moved so the
+                // first step after a cursor move sees its velocity
+                let last_target = match &self.drag {
+                    Some(drag) if drag.body == body && drag.point == point => drag.last_target,
+                    _ => target,
+                };
+                self.drag = Some(Drag {
+                    body,
+                    point,
+                    target,
+                    last_target,
+                })
+            }
+            SimCommand::Release => self.drag = None,
+            SimCommand::Pause => self.paused = true,
+            SimCommand::Resume => {
+                self.paused = false;
+                self.pending_steps = 0;
+            }
+            SimCommand::Step(n) => {
+                // Single-stepping only means something while paused
+                if self.paused {
+                    self.pending_steps = self.pending_steps.saturating_add(n);
+                }
+            }
+            SimCommand::SetParam(param) => self.set_param(param),
+            SimCommand::LoadScene(scene) => {
+                self.scene = scene;
+                self.reset();
+            }
+            SimCommand::Reset => self.reset(),
+        }
+    }
+
+    fn set_param(&mut self, param: SimParam) {
+        match param {
+            SimParam::Gravity(gravity) => {
+                self.softbodies.gravity = gravity;
+                self.remember(param);
+            }
+            SimParam::Wind(wind) => {
+                self.softbodies.wind = wind;
+                self.remember(param);
+            }
+            SimParam::TimeScale(scale) => {
+                self.time_scale = scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE)
+            }
+            SimParam::DragStiffness(stiffness) => self.drag_stiffness = stiffness,
+            SimParam::DragDamping(damping) => self.drag_damping = damping,
+            SimParam::Stiffness(stiffness) => {
+                self.softbodies.set_stiffness(stiffness);
+                self.remember(param);
+            }
+            SimParam::Damping(damping) => {
+                self.softbodies.set_damping(damping);
+                self.remember(param);
+            }
+            SimParam::Friction(friction) => {
+                self.softbodies.set_friction(friction);
+                self.remember(param);
+            }
+            SimParam::AreaStiffness(stiffness) => {
+                self.softbodies.set_area_stiffness(stiffness);
+                self.remember(param);
+            }
+            SimParam::Plasticity {
+                yield_strain,
+                creep,
+            } => {
+                self.softbodies.set_plasticity(yield_strain, creep);
+                self.remember(param);
+            }
+        }
+    }
+
+    fn remember(&mut self, param: SimParam) {
+        let kind = mem::discriminant(&param);
+        self.world_params.retain(|p| mem::discriminant(p) != kind);
+        self.world_params.push(param);
+    }
+
+    fn reset(&mut self) {
+        self.softbodies = self.scene.clone();
+        self.drag = None;
+        for param in self.world_params.clone() {
+            self.set_param(param);
+        }
+    }
+
+    fn apply_drag(&mut self, target_velocity: [f64; 2]) {
+        if let Some(drag) = &self.drag
+            && let Some(p) = self
+                .softbodies
+                .softbodies
+                .get_mut(drag.body)
+                .and_then(|sb| sb.points.get_mut(drag.point))
+        {
+            p.apply_target_spring(
+                drag.target,
+                target_velocity,
+                self.drag_stiffness,
+                self.drag_damping,
+            );
+        }
+    }
+
+    /// Advances one step if running, or consumes one queued single step while paused.
+    pub fn step(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        if self.paused {
+            self.pending_steps -= 1;
+        }
+
+        // Fast-forward is split into substeps no longer than a normal frame so
+        // the springs stay as stable as they are at 1x
+        let substeps = self.time_scale.ceil().max(1.0);
+        let dt = self.time_scale / substeps;
+
+        let mut target_velocity = [0.0, 0.0];
+        if let Some(drag) = &mut self.drag {
+            target_velocity = [
+                (drag.target[0] - drag.last_target[0]) / self.time_scale,
+                (drag.target[1] - drag.last_target[1]) / self.time_scale,
+            ];
+            drag.last_target = drag.target;
+        }
+
+        for _ in 0..substeps as usize {
+            self.apply_drag(target_velocity);
+            self.softbodies.update(&self.bounds, dt);
+        }
+    }
+}
+
+pub fn spawn(
+    softbodies: SoftBodyCollection,
+    bounds: [f64; 2],
+) -> (Sender<SimCommand>, SnapshotReader<SoftBodyCollection>) {
+    let (command_tx, command_rx) = mpsc::channel();
+    let (snapshot_writer, snapshot_reader) = snapshot::channel(&softbodies);
+    let simulator = Simulator::new(softbodies, bounds);
+
+    thread::spawn(move || run(simulator, command_rx, snapshot_writer));
+
+    (command_tx, snapshot_reader)
+}
+
+fn run(
+    mut simulator: Simulator,
+    commands: Receiver<SimCommand>,
+    mut snapshots: SnapshotWriter<SoftBodyCollection>,
+) {
+    loop {
+        // While paused there is nothing to step, so block until the UI asks for something
+        if !simulator.is_running() {
+            match commands.recv() {
+                Ok(command) => simulator.handle(command),
+                Err(_) => return,
+            }
+        }
+
+        loop {
+            match commands.try_recv() {
+                Ok(command) => simulator.handle(command),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        let running = simulator.is_running();
+        simulator.step();
+        snapshots.publish(&simulator.softbodies);
+
+        if running {
+            thread::sleep(STEP_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{Field, Uniform};
+    use crate::simulation::SoftBody;
+
+    /// How far one step of dragging with `stiffness` moves the held point.
+    fn pull(stiffness: f64) -> f64 {
+        let mut scene = SoftBodyCollection::new();
+        scene.add(SoftBody::new_square([100.0, 100.0], 50.0, 2));
+        let mut simulator = Simulator::new(scene, [1000.0, 1000.0]);
+        simulator.handle(SimCommand::SetParam(SimParam::Gravity([0.0, 0.0])));
+        simulator.handle(SimCommand::SetParam(SimParam::DragStiffness(stiffness)));
+        simulator.handle(SimCommand::Drag {
+            body: 0,
+            point: 0,
+            target: [95.0, 100.0],
+        });
+        simulator.step();
+        100.0 - simulator.softbodies.softbodies[0].points[0].position[0]
+    }
+
+    #[test]
+    fn drag_stiffness_sets_the_pull() {
+        assert!(pull(0.2) > pull(0.05));
+        assert!(pull(0.05) > 0.0);
+    }
+
+    #[test]
+    fn removing_another_body_keeps_the_drag() {
+        let mut scene = SoftBodyCollection::new();
+        scene.add(SoftBody::new_square([100.0, 100.0], 50.0, 2));
+        scene.add(SoftBody::new_square([300.0, 100.0], 50.0, 2));
+        scene.add(SoftBody::new_square([500.0, 100.0], 50.0, 2));
+        let mut simulator = Simulator::new(scene, [1000.0, 1000.0]);
+        let target = [295.0, 100.0];
+        simulator.handle(SimCommand::Drag {
+            body: 1,
+            point: 0,
+            target,
+        });
+
+        simulator.handle(SimCommand::Remove(0));
+        let drag = simulator.drag.as_ref().unwrap();
+        assert_eq!((drag.body, drag.point, drag.target), (0, 0, target));
+
+        simulator.handle(SimCommand::Remove(1));
+        assert!(simulator.drag.is_some());
+
+        simulator.handle(SimCommand::Remove(0));
+        assert!(simulator.drag.is_none());
+    }
+
+    #[test]
+    fn fields_can_be_switched_off_and_on() {
+        let mut scene = SoftBodyCollection::new();
+        scene.add(SoftBody::new_square([100.0, 100.0], 50.0, 2));
+        let mut simulator = Simulator::new(scene, [1000.0, 1000.0]);
+        simulator.handle(SimCommand::SetParam(SimParam::Gravity([0.0, 0.0])));
+        simulator.handle(SimCommand::AddField(Field::new(Uniform {
+            acceleration: [0.1, 0.0],
+        })));
+        let speed =
+            |simulator: &Simulator| simulator.softbodies.softbodies[0].points[0].velocity()[0];
+
+        simulator.handle(SimCommand::SetFieldEnabled(0, false));
+        // Out of range indices are ignored
+        simulator.handle(SimCommand::SetFieldEnabled(1, true));
+        simulator.step();
+        assert_eq!(speed(&simulator), 0.0);
+
+        simulator.handle(SimCommand::SetFieldEnabled(0, true));
+        simulator.step();
+        assert!(speed(&simulator) > 0.0);
+    }
+
+    #[test]
+    fn reset_keeps_world_settings() {
+        let mut simulator = Simulator::new(SoftBodyCollection::new(), [1000.0, 1000.0]);
+        simulator.handle(SimCommand::SetParam(SimParam::Gravity([0.1, 0.0])));
+        simulator.handle(SimCommand::Reset);
+        assert_eq!(simulator.softbodies.gravity, [0.1, 0.0]);
+    }
+
+    #[test]
+    fn paused_simulation_only_moves_by_single_steps() {
+        let mut scene = SoftBodyCollection::new();
+        scene.add(SoftBody::new_square([100.0, 100.0], 50.0, 2));
+        let mut simulator = Simulator::new(scene, [1000.0, 1000.0]);
+        let height =
+            |simulator: &Simulator| simulator.softbodies.softbodies[0].points[0].position[1];
+
+        simulator.handle(SimCommand::Pause);
+        simulator.step();
+        assert_eq!(height(&simulator), 100.0);
+
+        simulator.handle(SimCommand::Step(2));
+        simulator.step();
+        let after_one = height(&simulator);
+        assert!(after_one > 100.0);
+        assert!(simulator.is_running());
+        simulator.step();
+        assert!(!simulator.is_running());
+        simulator.step();
+        assert!(height(&simulator) > after_one);
+    }
+
+    #[test]
+    fn single_steps_do_not_overflow() {
+        let mut simulator = Simulator::new(SoftBodyCollection::new(), [1000.0, 1000.0]);
+        simulator.handle(SimCommand::Pause);
+        simulator.handle(SimCommand::Step(u32::MAX));
+        simulator.handle(SimCommand::Step(u32::MAX));
+        assert_eq!(simulator.pending_steps, u32::MAX);
+    }
+
+    #[test]
+    fn time_scale_is_clamped() {
+        let mut simulator = Simulator::new(SoftBodyCollection::new(), [1000.0, 1000.0]);
+        for (scale, expected) in [(2.0, 2.0), (100.0, MAX_TIME_SCALE), (0.0, MIN_TIME_SCALE)] {
+            simulator.handle(SimCommand::SetParam(SimParam::TimeScale(scale)));
+            assert_eq!(simulator.time_scale, expected);
+        }
+    }
+}
@@ -1,10 +1,124 @@
-const GRAVITY: [f64; 2] = [0.0, 0.098];
+use crate::broadphase::SpatialGrid;
+use crate::collider::Collider;
+use crate::fem::FemElement;
+use crate::field::Field;
+use crate::fluid::Fluid;
+use crate::geometry;
+use crate::implicit::{self, SpringJacobian};
+use crate::rigid::{self, RigidBody};
+
+pub const DEFAULT_GRAVITY: [f64; 2] = [0.0, 0.098];
+pub const DEFAULT_STIFFNESS: f64 = 0.6; // less stiff = more fluid
+pub const DEFAULT_DAMPING: f64 = 0.4; // less damping = more fluid
+pub const DEFAULT_FRICTION: f64 = 0.99;
+pub const DEFAULT_AREA_STIFFNESS: f64 = 0.2;
+// extra push on triangles turned inside out, so they unfold before anything else
+const INVERSION_BOOST: f64 = 2.0;
+pub const DEFAULT_BENDING_STIFFNESS: f64 = 0.05;
+const DEFAULT_BENDING_DAMPING: f64 = 0.2;
+const DEFAULT_COLOR: [f32; 4] = [0.9, 0.3, 0.3, 1.0];
+// gas pressure of a blob per unit of its rest area
+const BLOB_PRESSURE: f64 = 0.01;
+// aerodynamic force per unit of edge length and squared speed, across and along the flow
+pub const DEFAULT_DRAG: f64 = 0.005;
+pub const DEFAULT_LIFT: f64 = 0.005;
+
+/// How a body's points are stepped forward in time.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Integrator {
+    // forces from the start of the step, with velocities clamped to keep stiff springs in check
+    Explicit,
+    // springs solved for the end of the step, so they can be made far stiffer
+    Implicit,
+}
+
+/// Spring and point settings applied to a whole body when it is built.
+#[derive(Clone, Copy)]
+pub struct Material {
+    pub name: &'static str,
+    pub stiffness: f64,
+    pub damping: f64,
+    pub friction: f64,
+    // multiplier on the mass of every point
+    pub mass: f64,
+    pub creep: f64,
+    // elasticity used when the body is built from finite elements instead of springs
+    pub youngs_modulus: f64,
+    pub poisson_ratio: f64,
+    pub integrator: Integrator,
+    pub color: [f32; 4],
+}
+
+pub const MATERIALS: [Material; 5] = [
+    Material {
+        name: "Jelly",
+        stiffness: DEFAULT_STIFFNESS,
+        damping: DEFAULT_DAMPING,
+        friction: DEFAULT_FRICTION,
+        mass: 1.0,
+        creep: 0.0,
+        youngs_modulus: 0.3,
+        poisson_ratio: 0.3,
+        integrator: Integrator::Explicit,
+        color: [0.9, 0.3, 0.5, 1.0],
+    },
+    Material {
+        name: "Rubber",
+        stiffness: 0.9,
+        damping: 0.5,
+        friction: 0.995,
+        mass: 1.0,
+        creep: 0.0,
+        youngs_modulus: 0.6,
+        poisson_ratio: 0.45,
+        integrator: Integrator::Explicit,
+        color: [0.3, 0.7, 0.9, 1.0],
+    },
+    Material {
+        name: "Clay",
+        stiffness: 0.8,
+        damping: 0.4,
+        friction: 0.97,
+        mass: 1.5,
+        creep: 0.05,
+        youngs_modulus: 0.4,
+        poisson_ratio: 0.2,
+        integrator: Integrator::Explicit,
+        color: [0.7, 0.5, 0.3, 1.0],
+    },
+    Material {
+        name: "Foam",
+        stiffness: 0.25,
+        damping: 0.3,
+        friction: 0.98,
+        mass: 0.5,
+        creep: 0.0,
+        youngs_modulus: 0.15,
+        poisson_ratio: 0.1,
+        integrator: Integrator::Explicit,
+        color: [0.95, 0.95, 0.8, 1.0],
+    },
+    Material {
+        name: "Wood",
+        stiffness: 40.0,
+        damping: 0.5,
+        friction: 0.97,
+        mass: 1.0,
+        creep: 0.0,
+        youngs_modulus: 4.0,
+        poisson_ratio: 0.3,
+        integrator: Integrator::Implicit,
+        color: [0.55, 0.35, 0.2, 1.0],
+    },
+];
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct Point {
     pub position: [f64; 2],
     force: [f64; 2],
     velocity: [f64; 2],
+    // force applied during the last step, kept for debugging after `force` resets
+    last_force: [f64; 2],
     max_velocity: f64,
     mass: f64,
     friction: f64,
@@ -16,25 +130,53 @@ impl Point {
             position,
             force: [0.0, 0.0],
             velocity: [0.0, 0.0],
+            last_force: [0.0, 0.0],
             max_velocity,
             mass,
-            friction: 0.99,
+            friction: DEFAULT_FRICTION,
         }
     }
 
-    pub fn apply_gravity(&mut self) {
-        self.force[0] += GRAVITY[0];
-        self.force[1] += GRAVITY[1];
+    pub fn velocity(&self) -> [f64; 2] {
+        self.velocity
     }
 
-    pub fn apply_force(&mut self) {
-        self.velocity[0] += self.force[0] / self.mass;
-        self.velocity[1] += self.force[1] / self.mass;
+    pub fn last_force(&self) -> [f64; 2] {
+        self.last_force
     }
 
-    pub fn apply_friction(&mut self) {
-        self.velocity[0] *= self.friction;
-        self.velocity[1] *= self.friction;
+    /// Weight of the point; `gravity` is an acceleration, so every point falls
+    /// alike whatever its mass.
+    pub fn apply_gravity(&mut self, gravity: [f64; 2]) {
+        self.force[0] += gravity[0] * self.mass;
+        self.force[1] += gravity[1] * self.mass;
+    }
+
+    pub fn apply_force(&mut self, dt: f64) {
+        self.velocity[0] += self.force[0] / self.mass * dt;
+        self.velocity[1] += self.force[1] / self.mass * dt;
+    }
+
+    pub fn apply_friction(&mut self, dt: f64) {
+        // friction is the velocity kept per full step, so scale it for partial steps
+        let friction = self.friction.powf(dt);
+        self.velocity[0] *= friction;
+        self.velocity[1] *= friction;
+    }
+
+    /// Pulls the point toward a moving target with a damped spring. Damping acts on
+    /// the velocity relative to the target, so the point can keep up with it.
+    pub fn apply_target_spring(
+        &mut self,
+        target: [f64; 2],
+        target_velocity: [f64; 2],
+        stiffness: f64,
+        damping: f64,
+    ) {
+        for axis in 0..2 {
+            self.force[axis] += stiffness * (target[axis] - self.position[axis])
+                + damping * (target_velocity[axis] - self.velocity[axis]);
+        }
     }
 
     pub fn clamp_velocity(&mut self) {
@@ -46,17 +188,18 @@ impl Point {
         }
     }
 
-    pub fn apply_all(&mut self) {
-        self.apply_gravity();
-        self.apply_force();
-        self.apply_friction();
+    pub fn apply_all(&mut self, gravity: [f64; 2], dt: f64) {
+        self.apply_gravity(gravity);
+        self.apply_force(dt);
+        self.apply_friction(dt);
         self.clamp_velocity();
     }
 
-    pub fn update(&mut self) {
-        self.position[0] += self.velocity[0];
-        self.position[1] += self.velocity[1];
+    pub fn update(&mut self, dt: f64) {
+        self.position[0] += self.velocity[0] * dt;
+        self.position[1] += self.velocity[1] * dt;
 
+        self.last_force = self.force;
         self.force = [0.0, 0.0];
     }
 
@@ -77,6 +220,23 @@ impl Point {
             self.velocity[1] *= -1.0;
         }
     }
+
+    /// Moves the point out of a rigid body, trading momentum with it.
+    pub fn handle_rigid_collision(&mut self, body: &mut RigidBody) {
+        body.collide_point(&mut self.position, &mut self.velocity, self.mass);
+    }
+
+    /// Moves the point out of a static collider, bouncing off its surface.
+    pub fn handle_collider_collision(&mut self, collider: &Collider) {
+        if let Some((surface, normal)) = collider.push_out(self.position) {
+            self.position = surface;
+            let normal_speed = self.velocity[0] * normal[0] + self.velocity[1] * normal[1];
+            if normal_speed < 0.0 {
+                self.velocity[0] -= 2.0 * normal_speed * normal[0];
+                self.velocity[1] -= 2.0 * normal_speed * normal[1];
+            }
+        }
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -84,8 +244,14 @@ pub struct Spring {
     pub point1: usize,
     pub point2: usize,
     pub rest_length: f64,
+    // rest length the spring was built with, before any plastic flow
+    original_length: f64,
     stiffness: f64,
     damping: f64,
+    // strain beyond which the rest length starts to creep toward the current length
+    yield_strain: f64,
+    // fraction of the excess strain absorbed per step; 0 keeps the spring elastic
+    creep: f64,
 }
 
 impl Spring {
@@ -94,16 +260,121 @@ impl Spring {
             point1,
             point2,
             rest_length,
-            stiffness: 0.6, // less stiff = more fluid
-            damping: 0.4,   // less damping = more fluid
+            original_length: rest_length,
+            stiffness: DEFAULT_STIFFNESS,
+            damping: DEFAULT_DAMPING,
+            yield_strain: 0.1,
+            creep: 0.0,
         }
     }
+
+    pub fn length(&self, points: &[Point]) -> f64 {
+        let p1 = points[self.point1].position;
+        let p2 = points[self.point2].position;
+        ((p2[0] - p1[0]).powi(2) + (p2[1] - p1[1]).powi(2)).sqrt()
+    }
+
+    /// Relative stretch: negative when compressed, positive under tension.
+    pub fn strain(&self, points: &[Point]) -> f64 {
+        (self.length(points) - self.rest_length) / self.rest_length
+    }
+
+    /// Magnitude of the force the spring currently exerts on each end.
+    pub fn force(&self, points: &[Point]) -> f64 {
+        (self.stiffness * (self.length(points) - self.rest_length) * self.damping).abs()
+    }
+
+    /// How far the rest length has permanently drifted from the original.
+    pub fn plastic_strain(&self) -> f64 {
+        self.rest_length / self.original_length - 1.0
+    }
+}
+
+/// Holds the signed area of a triangle near its rest value, so lattices keep
+/// their volume and don't fold inside out under load.
+#[derive(Clone, PartialEq)]
+pub struct AreaConstraint {
+    pub corners: [usize; 3],
+    pub rest_area: f64,
+    pub stiffness: f64,
+}
+
+impl AreaConstraint {
+    pub fn area(&self, points: &[Point]) -> f64 {
+        let [a, b, c] = self.corners.map(|i| points[i].position);
+        0.5 * ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]))
+    }
+
+    /// True once the triangle has flipped relative to its rest orientation.
+    pub fn inverted(&self, points: &[Point]) -> bool {
+        self.area(points) * self.rest_area < 0.0
+    }
+}
+
+/// Resists bending at `pivot`: holds the angle from `point1` around to `point2`
+/// near its rest value. Stiffness is scaled by both arm lengths, so it behaves
+/// like a spring of the same stiffness pulling the ends sideways.
+#[derive(Clone, PartialEq)]
+pub struct AngularSpring {
+    pub point1: usize,
+    pub pivot: usize,
+    pub point2: usize,
+    pub rest_angle: f64,
+    pub stiffness: f64,
+    // fraction of the joint's angular velocity removed, relative to the stiffness
+    pub damping: f64,
+}
+
+impl AngularSpring {
+    pub fn new(point1: usize, pivot: usize, point2: usize, rest_angle: f64) -> Self {
+        AngularSpring {
+            point1,
+            pivot,
+            point2,
+            rest_angle,
+            stiffness: DEFAULT_BENDING_STIFFNESS,
+            damping: DEFAULT_BENDING_DAMPING,
+        }
+    }
+
+    /// Signed angle at the pivot, from the first arm to the second.
+    pub fn angle(&self, points: &[Point]) -> f64 {
+        let (a, b) = self.arms(points);
+        (a[0] * b[1] - a[1] * b[0]).atan2(a[0] * b[0] + a[1] * b[1])
+    }
+
+    fn arms(&self, points: &[Point]) -> ([f64; 2], [f64; 2]) {
+        let p = points[self.pivot].position;
+        let a = points[self.point1].position;
+        let b = points[self.point2].position;
+        ([a[0] - p[0], a[1] - p[1]], [b[0] - p[0], b[1] - p[1]])
+    }
 }
 
 #[derive(Clone, PartialEq)]
 pub struct SoftBody {
     pub points: Vec<Point>,
     pub springs: Vec<Spring>,
+    pub angular_springs: Vec<AngularSpring>,
+    pub area_constraints: Vec<AreaConstraint>,
+    // finite elements, used in place of springs by bodies built with `with_fem`
+    pub elements: Vec<FemElement>,
+    // indices of the points on the outer edge, in order around the body
+    pub boundary: Vec<usize>,
+    // inner edges around holes, in the same form as `boundary`
+    pub holes: Vec<Vec<usize>>,
+    // triangles covering the body, for bodies built from a mesh
+    pub triangles: Vec<[usize; 3]>,
+    pub color: [f32; 4],
+    // gas pressure pushing the boundary outward; 0 for solid bodies
+    pub pressure: f64,
+    pub integrator: Integrator,
+    // aerodynamic coefficients of the boundary edges; 0 leaves the air to the
+    // points' friction
+    pub drag: f64,
+    pub lift: f64,
+    // multiplier on the world's gravity for this body; negative floats up
+    pub gravity_scale: f64,
 }
 
 impl SoftBody {
@@ -111,90 +382,395 @@ impl SoftBody {
         SoftBody {
             points: Vec::new(),
             springs: Vec::new(),
+            angular_springs: Vec::new(),
+            area_constraints: Vec::new(),
+            elements: Vec::new(),
+            boundary: Vec::new(),
+            holes: Vec::new(),
+            triangles: Vec::new(),
+            color: DEFAULT_COLOR,
+            pressure: 0.0,
+            integrator: Integrator::Explicit,
+            drag: 0.0,
+            lift: 0.0,
+            gravity_scale: 1.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_material(mut self, material: &Material) -> Self {
+        for spring in &mut self.springs {
+            spring.stiffness = material.stiffness;
+            spring.damping = material.damping;
+            spring.creep = material.creep;
+        }
+        for point in &mut self.points {
+            point.friction = material.friction;
+            point.mass *= material.mass;
+        }
+        self.color = material.color;
+        self.integrator = material.integrator;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, scale: f64) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    /// Lets the air act on the boundary edges, with the given drag and lift
+    /// coefficients, in place of the points' uniform friction.
+    pub fn with_aerodynamics(mut self, drag: f64, lift: f64) -> Self {
+        self.drag = drag;
+        self.lift = lift;
+        for point in &mut self.points {
+            point.friction = 1.0;
+        }
+        self
+    }
+
+    /// Replaces the springs with finite elements on the body's triangles and
+    /// lumps each element's mass onto its corners, so the body weighs about the
+    /// same as its spring version. Bodies without triangles, like ropes and
+    /// blobs, are left as they are.
+    pub fn with_fem(mut self, material: &Material) -> Self {
+        let positions: Vec<[f64; 2]> = self.points.iter().map(|p| p.position).collect();
+        self.elements = self
+            .triangles
+            .iter()
+            .filter_map(|&corners| {
+                FemElement::new(
+                    corners,
+                    corners.map(|i| positions[i]),
+                    material.youngs_modulus,
+                    material.poisson_ratio,
+                )
+            })
+            .collect();
+        if self.elements.is_empty() {
+            return self;
+        }
+
+        let total_area: f64 = self.elements.iter().map(|e| e.rest_area).sum();
+        let density = material.mass * self.points.len() as f64 / total_area;
+        for point in &mut self.points {
+            point.mass = 0.0;
+        }
+        for element in &self.elements {
+            for &i in &element.corners {
+                self.points[i].mass += density * element.rest_area / 3.0;
+            }
+        }
+        // Thin triangles are stiff across their short side; weigh their corners
+        // down enough that a full-frame step can't overshoot
+        let mut stiffness = vec![0.0; self.points.len()];
+        for element in &self.elements {
+            for (&i, k) in element.corners.iter().zip(element.corner_stiffness()) {
+                stiffness[i] += k;
+            }
+        }
+        for (point, k) in self.points.iter_mut().zip(stiffness) {
+            point.mass = point.mass.max(k);
+        }
+        // Points outside every element still need some mass to move
+        for point in &mut self.points {
+            if point.mass == 0.0 {
+                point.mass = material.mass;
+            }
+        }
+
+        self.springs.clear();
+        self.angular_springs.clear();
+        self.area_constraints.clear();
+        self
+    }
+
+    /// Starts every point moving at `velocity`.
+    pub fn with_velocity(mut self, velocity: [f64; 2]) -> Self {
+        for point in &mut self.points {
+            point.velocity = velocity;
+        }
+        self
+    }
+
+    /// Adds a spring between two points at their current distance, unless
+    /// they're already joined; on small polygons the braces can land on
+    /// neighbours, and a second spring would double that edge's stiffness.
+    fn connect(&mut self, point1: usize, point2: usize) {
+        let joined = |s: &Spring| {
+            (s.point1, s.point2) == (point1, point2) || (s.point1, s.point2) == (point2, point1)
+        };
+        if self.springs.iter().any(joined) {
+            return;
+        }
+        let p1 = self.points[point1].position;
+        let p2 = self.points[point2].position;
+        let length = ((p2[0] - p1[0]).powi(2) + (p2[1] - p1[1]).powi(2)).sqrt();
+        self.springs.push(Spring::new(point1, point2, length));
+    }
+
+    /// Adds an area constraint for every triangle, at its current area.
+    /// Slivers with next to no area are left out since they can't push back.
+    pub fn constrain_areas(&mut self) {
+        self.area_constraints = self
+            .triangles
+            .iter()
+            .map(|&corners| {
+                let mut constraint = AreaConstraint {
+                    corners,
+                    rest_area: 0.0,
+                    stiffness: DEFAULT_AREA_STIFFNESS,
+                };
+                constraint.rest_area = constraint.area(&self.points);
+                constraint
+            })
+            .filter(|c| c.rest_area.abs() > f64::EPSILON)
+            .collect();
+    }
+
+    /// Adds an angular spring at `pivot` that keeps its current angle.
+    fn brace(&mut self, point1: usize, pivot: usize, point2: usize) {
+        let mut spring = AngularSpring::new(point1, pivot, point2, 0.0);
+        spring.rest_angle = spring.angle(&self.points);
+        self.angular_springs.push(spring);
+    }
+
+    /// Points evenly spaced on a circle, linked into a closed boundary ring.
+    fn ring(center: [f64; 2], radius: f64, segments: usize) -> Self {
+        let mut soft_body = SoftBody::new();
+        let spacing = 2.0 * std::f64::consts::PI * radius / segments as f64;
+        for i in 0..segments {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / segments as f64;
+            soft_body.points.push(Point::new(
+                [
+                    center[0] + radius * angle.cos(),
+                    center[1] + radius * angle.sin(),
+                ],
+                1.0,
+                spacing.min(radius) * 0.2,
+            ));
+        }
+        for i in 0..segments {
+            soft_body.connect(i, (i + 1) % segments);
+        }
+        soft_body.boundary = (0..segments).collect();
+        soft_body
+    }
+
+    /// A regular polygon held in shape by spokes to a heavier hub point.
+    pub fn new_regular_polygon(center: [f64; 2], radius: f64, sides: usize) -> Self {
+        let sides = sides.max(3);
+        let mut soft_body = SoftBody::ring(center, radius, sides);
+        for i in 0..sides {
+            soft_body.connect(i, (i + 2) % sides);
+        }
+
+        // The hub carries every spoke, so give it the mass to stay stable
+        let hub = soft_body.points.len();
+        let max_velocity = soft_body.points[0].max_velocity;
+        let hub_mass = (sides as f64 / 4.0).max(1.0);
+        soft_body
+            .points
+            .push(Point::new(center, hub_mass, max_velocity));
+        for i in 0..sides {
+            soft_body.connect(i, hub);
+            soft_body.triangles.push([i, (i + 1) % sides, hub]);
+        }
+        soft_body.constrain_areas();
+        soft_body
+    }
+
+    /// A disc of `segments` rim points, braced by spokes to a hub and by
+    /// springs across each pair of neighbours.
+    pub fn new_circle(center: [f64; 2], radius: f64, segments: usize) -> Self {
+        SoftBody::new_regular_polygon(center, radius, segments.max(6))
+    }
+
+    /// A hollow tube: inner and outer rings joined by radial and diagonal
+    /// springs, with the inside left open as a hole.
+    pub fn new_ring(
+        center: [f64; 2],
+        inner_radius: f64,
+        outer_radius: f64,
+        segments: usize,
+    ) -> Self {
+        let segments = segments.max(3);
+        let mut soft_body = SoftBody::ring(center, outer_radius, segments);
+        let inner = SoftBody::ring(center, inner_radius, segments);
+        // Sized to the closest neighbours, which may be round either rim or
+        // across the tube, so no two points start inside each other's reach
+        let max_velocity = soft_body.points[0]
+            .max_velocity
+            .min(inner.points[0].max_velocity)
+            .min((outer_radius - inner_radius) * 0.2);
+        for point in &mut soft_body.points {
+            point.max_velocity = max_velocity;
+        }
+        soft_body
+            .points
+            .extend(inner.points.iter().map(|p| Point { max_velocity, ..*p }));
+
+        let outer = |i: usize| i % segments;
+        let inner = |i: usize| segments + i % segments;
+        for i in 0..segments {
+            soft_body.connect(inner(i), inner(i + 1));
+            soft_body.connect(outer(i), inner(i));
+            soft_body.connect(outer(i), inner(i + 1));
+            soft_body.connect(inner(i), outer(i + 1));
+            // Skipping a point along the rim stops the tube folding flat
+            soft_body.connect(outer(i), outer(i + 2));
+            soft_body.triangles.extend([
+                [outer(i), outer(i + 1), inner(i)],
+                [inner(i), outer(i + 1), inner(i + 1)],
+            ]);
+        }
+        soft_body.holes = vec![(segments..segments * 2).collect()];
+        soft_body.constrain_areas();
+        soft_body
+    }
+
+    /// A hollow ring kept inflated by gas pressure rather than internal springs.
+    pub fn new_blob(center: [f64; 2], radius: f64, segments: usize) -> Self {
+        let segments = segments.max(3);
+        let mut soft_body = SoftBody::ring(center, radius, segments);
+        soft_body.pressure = BLOB_PRESSURE * geometry::signed_area(&soft_body.outline()).abs();
+        soft_body
+    }
+
+    /// A chain of points between `start` and `end`, with angular springs at
+    /// every joint so it resists bending.
+    pub fn new_rope(start: [f64; 2], end: [f64; 2], segments: usize) -> Self {
+        let segments = segments.max(1);
+        let mut soft_body = SoftBody::new();
+        let length = ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt();
+        let max_velocity = length / segments as f64 * 0.2;
+        for i in 0..=segments {
+            let t = i as f64 / segments as f64;
+            soft_body.points.push(Point::new(
+                [
+                    start[0] + (end[0] - start[0]) * t,
+                    start[1] + (end[1] - start[1]) * t,
+                ],
+                1.0,
+                max_velocity,
+            ));
+        }
+        for i in 0..segments {
+            soft_body.connect(i, i + 1);
         }
+        for i in 1..segments {
+            soft_body.brace(i - 1, i, i + 1);
+        }
+        // Out along the chain and back, so the outline traces the rope
+        soft_body.boundary = (0..=segments).chain((1..segments).rev()).collect();
+        soft_body
     }
 
     pub fn new_square(pos: [f64; 2], size: f64, faces: i32) -> Self {
+        SoftBody::new_rectangle(pos, size, size, faces, faces)
+    }
+
+    /// A `width` by `height` lattice of `nx` by `ny` cells, braced across every cell.
+    pub fn new_rectangle(pos: [f64; 2], width: f64, height: f64, nx: i32, ny: i32) -> Self {
         let mut soft_body = SoftBody::new();
         let mass = 1.0;
-        let faces = faces + 1;
-        let valocity_max = size / faces as f64 * 0.2;
-        for i in 0..faces {
-            for j in 0..faces {
-                let x = pos[0] + (i as f64) * size / (faces - 1) as f64;
-                let y = pos[1] + (j as f64) * size / (faces - 1) as f64;
+        let (columns, rows) = (nx.max(1) as usize + 1, ny.max(1) as usize + 1);
+        let (cell_w, cell_h) = (width / (columns - 1) as f64, height / (rows - 1) as f64);
+        let valocity_max = (width / columns as f64).min(height / rows as f64) * 0.2;
+        let idx = |i: usize, j: usize| i * rows + j;
+        for i in 0..columns {
+            for j in 0..rows {
+                let x = pos[0] + i as f64 * cell_w;
+                let y = pos[1] + j as f64 * cell_h;
                 soft_body
                     .points
                     .push(Point::new([x, y], mass, valocity_max));
             }
         }
 
-        for i in 0..faces {
-            for j in 0..faces {
-                let idx = (i * faces + j) as usize;
-                if j < faces - 1 {
-                    let right_idx = idx + 1;
+        for i in 0..columns {
+            for j in 0..rows {
+                if j < rows - 1 {
                     soft_body
                         .springs
-                        .push(Spring::new(idx, right_idx, size / (faces - 1) as f64));
+                        .push(Spring::new(idx(i, j), idx(i, j + 1), cell_h));
                 }
-                if i < faces - 1 {
-                    let down_idx = idx + faces as usize;
+                if i < columns - 1 {
                     soft_body
                         .springs
-                        .push(Spring::new(idx, down_idx, size / (faces - 1) as f64));
+                        .push(Spring::new(idx(i, j), idx(i + 1, j), cell_w));
                 }
             }
         }
 
-        for i in 0..faces {
-            for j in 0..faces {
-                let idx = (i * faces + j) as usize;
-                if i < faces - 1 && j < faces - 1 {
-                    let down_right_idx = idx + faces as usize + 1;
-                    soft_body.springs.push(Spring::new(
-                        idx,
-                        down_right_idx,
-                        (size / (faces - 1) as f64) * (2f64).sqrt(),
-                    ));
+        let diagonal = cell_w.hypot(cell_h);
+        for i in 0..columns - 1 {
+            for j in 0..rows {
+                if j < rows - 1 {
+                    soft_body
+                        .springs
+                        .push(Spring::new(idx(i, j), idx(i + 1, j + 1), diagonal));
                 }
-                if i < faces - 1 && j > 0 {
-                    let down_left_idx = idx + faces as usize - 1;
-                    soft_body.springs.push(Spring::new(
-                        idx,
-                        down_left_idx,
-                        (size / (faces - 1) as f64) * (2f64).sqrt(),
-                    ));
+                if j > 0 {
+                    soft_body
+                        .springs
+                        .push(Spring::new(idx(i, j), idx(i + 1, j - 1), diagonal));
                 }
             }
         }
 
-        soft_body
-    }
+        for i in 0..columns - 1 {
+            for j in 0..rows - 1 {
+                soft_body.triangles.extend([
+                    [idx(i, j), idx(i + 1, j), idx(i + 1, j + 1)],
+                    [idx(i, j), idx(i + 1, j + 1), idx(i, j + 1)],
+                ]);
+            }
+        }
 
-    #[allow(dead_code)]
-    pub fn new_triangle(pos: [f64; 2]) -> Self {
-        let mut soft_body = SoftBody::new();
-        let idx1 = soft_body.points.len();
+        // Walk the edge of the grid: top, right, bottom, then left
         soft_body
-            .points
-            .push(Point::new([pos[0], pos[1] - 15.0], 1.0, 100.0));
-        let idx2 = soft_body.points.len();
+            .boundary
+            .extend((0..columns - 1).map(|i| idx(i, 0)));
         soft_body
-            .points
-            .push(Point::new([pos[0] + 20.0, pos[1]], 1.0, 100.0));
-        let idx3 = soft_body.points.len();
+            .boundary
+            .extend((0..rows - 1).map(|j| idx(columns - 1, j)));
         soft_body
-            .points
-            .push(Point::new([pos[0] - 10.0, pos[1]], 1.0, 100.0));
-
-        soft_body.springs.push(Spring::new(idx1, idx2, 100.0));
-        soft_body.springs.push(Spring::new(idx2, idx3, 100.0));
-        soft_body.springs.push(Spring::new(idx3, idx1, 100.0));
+            .boundary
+            .extend((1..columns).rev().map(|i| idx(i, rows - 1)));
+        soft_body
+            .boundary
+            .extend((1..rows).rev().map(|j| idx(0, j)));
 
         soft_body
     }
 
+    /// Positions of the outer edge, falling back to the convex hull for bodies
+    /// built without a boundary.
+    pub fn outline(&self) -> Vec<[f64; 2]> {
+        let positions: Vec<[f64; 2]> = self.points.iter().map(|p| p.position).collect();
+        if self.boundary.is_empty() {
+            geometry::convex_hull(&positions)
+                .into_iter()
+                .map(|i| positions[i])
+                .collect()
+        } else {
+            self.boundary.iter().map(|&i| positions[i]).collect()
+        }
+    }
+
+    pub fn centroid(&self) -> [f64; 2] {
+        let n = self.points.len().max(1) as f64;
+        let (x, y) = self.points.iter().fold((0.0, 0.0), |(x, y), p| {
+            (x + p.position[0], y + p.position[1])
+        });
+        [x / n, y / n]
+    }
+
     pub fn apply_spring_force(&mut self, spring_index: usize) {
         let spring = &self.springs[spring_index];
         let (i1, i2) = (spring.point1, spring.point2);
@@ -224,6 +800,13 @@ impl SoftBody {
         p1.force[1] += fy * spring.damping;
         p2.force[0] -= fx * spring.damping;
         p2.force[1] -= fy * spring.damping;
+
+        // Past the yield point the spring gives way and keeps part of the deformation
+        let excess = stretch.abs() - spring.yield_strain * spring.rest_length;
+        if spring.creep > 0.0 && excess > 0.0 {
+            let flow = excess * spring.creep * stretch.signum();
+            self.springs[spring_index].rest_length += flow;
+        }
     }
 
     pub fn self_point_collision(&mut self, point_index1: usize, point_index2: usize) {
@@ -253,45 +836,235 @@ impl SoftBody {
         }
     }
 
-    pub fn point_collision(&mut self, point1: usize, point2: &mut Point) {
-        let p1 = &mut self.points[point1];
-        let p2 = point2;
+    pub fn aabb(&self) -> [f64; 4] {
+        self.points.iter().fold(
+            [f64::MAX, f64::MAX, f64::MIN, f64::MIN],
+            |[min_x, min_y, max_x, max_y], p| {
+                [
+                    min_x.min(p.position[0]),
+                    min_y.min(p.position[1]),
+                    max_x.max(p.position[0]),
+                    max_y.max(p.position[1]),
+                ]
+            },
+        )
+    }
 
-        let dx = p2.position[0] - p1.position[0];
-        let dy = p2.position[1] - p1.position[1];
-        let dist_sq = dx * dx + dy * dy;
-        if dist_sq == 0.0 {
+    /// Pushes the corners of a triangle along the directions that grow (or
+    /// shrink) its area fastest, in proportion to the relative area error.
+    pub fn apply_area_force(&mut self, constraint_index: usize) {
+        let constraint = &self.area_constraints[constraint_index];
+        let [a, b, c] = constraint.corners;
+        let [pa, pb, pc] = constraint.corners.map(|i| self.points[i].position);
+
+        let error =
+            (constraint.area(&self.points) - constraint.rest_area) / constraint.rest_area.abs();
+        let mut stiffness = constraint.stiffness;
+        if constraint.inverted(&self.points) {
+            stiffness *= INVERSION_BOOST;
+        }
+        // Clamped so a crushed triangle can't fling its corners
+        let scale = -stiffness * error.clamp(-1.0, 1.0);
+
+        // Gradient of the signed area with respect to each corner
+        let gradients = [
+            (a, [0.5 * (pb[1] - pc[1]), 0.5 * (pc[0] - pb[0])]),
+            (b, [0.5 * (pc[1] - pa[1]), 0.5 * (pa[0] - pc[0])]),
+            (c, [0.5 * (pa[1] - pb[1]), 0.5 * (pb[0] - pa[0])]),
+        ];
+        for (i, gradient) in gradients {
+            self.points[i].force[0] += scale * gradient[0];
+            self.points[i].force[1] += scale * gradient[1];
+        }
+    }
+
+    /// Bends the two arms of an angular spring back toward its rest angle, with
+    /// an equal and opposite reaction on the pivot.
+    pub fn apply_angular_spring_force(&mut self, spring_index: usize) {
+        let spring = &self.angular_springs[spring_index];
+        let (a, b) = spring.arms(&self.points);
+        let (len_a_sq, len_b_sq) = (a[0] * a[0] + a[1] * a[1], b[0] * b[0] + b[1] * b[1]);
+        if len_a_sq == 0.0 || len_b_sq == 0.0 {
             return;
         }
-        let distance = dist_sq.sqrt();
 
-        let radius = (p1.max_velocity + p2.max_velocity) * 2.5;
-        if distance < radius {
-            let overlap = radius - distance;
-            let force_mag = overlap * 0.5;
-            p1.force[0] -= force_mag * dx / distance;
-            p1.force[1] -= force_mag * dy / distance;
-            p2.force[0] += force_mag * dx / distance;
-            p2.force[1] += force_mag * dy / distance;
+        // How the angle changes as each end moves
+        let grad_a = [a[1] / len_a_sq, -a[0] / len_a_sq];
+        let grad_b = [-b[1] / len_b_sq, b[0] / len_b_sq];
+
+        let pivot = &self.points[spring.pivot];
+        let (p1, p2) = (&self.points[spring.point1], &self.points[spring.point2]);
+        let angular_velocity = grad_a[0] * (p1.velocity[0] - pivot.velocity[0])
+            + grad_a[1] * (p1.velocity[1] - pivot.velocity[1])
+            + grad_b[0] * (p2.velocity[0] - pivot.velocity[0])
+            + grad_b[1] * (p2.velocity[1] - pivot.velocity[1]);
+
+        // Keep the error in -pi..pi so a joint bent past straight doesn't flip
+        let error = (spring.angle(&self.points) - spring.rest_angle + std::f64::consts::PI)
+            .rem_euclid(std::f64::consts::TAU)
+            - std::f64::consts::PI;
+        let scale = spring.stiffness * (len_a_sq * len_b_sq).sqrt();
+        let torque = -scale * (error + spring.damping * angular_velocity);
+
+        let (i1, pivot, i2) = (spring.point1, spring.pivot, spring.point2);
+        for axis in 0..2 {
+            let fa = torque * grad_a[axis];
+            let fb = torque * grad_b[axis];
+            self.points[i1].force[axis] += fa;
+            self.points[i2].force[axis] += fb;
+            self.points[pivot].force[axis] -= fa + fb;
         }
     }
 
-    pub fn softbody_collision(&mut self, other: &mut SoftBody) {
-        for i in 0..self.points.len() {
-            for j in 0..other.points.len() {
-                self.point_collision(i, &mut other.points[j]);
+    pub fn apply_element_force(&mut self, element_index: usize) {
+        let element = &self.elements[element_index];
+        let positions = element.corners.map(|i| self.points[i].position);
+        let velocities = element.corners.map(|i| self.points[i].velocity);
+        let forces = element.forces(positions, velocities);
+        for (&i, force) in element.corners.iter().zip(forces) {
+            self.points[i].force[0] += force[0];
+            self.points[i].force[1] += force[1];
+        }
+    }
+
+    /// Pushes each boundary edge outward in proportion to its length and the
+    /// inverse of the enclosed area, so the body resists being squashed.
+    pub fn apply_pressure(&mut self) {
+        let n = self.boundary.len();
+        if self.pressure == 0.0 || n < 3 {
+            return;
+        }
+        let area = geometry::signed_area(&self.outline());
+        // orientation of the boundary decides which side of an edge is outside
+        let side = area.signum();
+        let per_length = self.pressure / area.abs().max(1.0);
+
+        for k in 0..n {
+            let (i, j) = (self.boundary[k], self.boundary[(k + 1) % n]);
+            let a = self.points[i].position;
+            let b = self.points[j].position;
+            // (dy, -dx) is the edge vector scaled by its length, turned outward
+            let fx = side * (b[1] - a[1]) * per_length * 0.5;
+            let fy = side * (a[0] - b[0]) * per_length * 0.5;
+            for index in [i, j] {
+                self.points[index].force[0] += fx;
+                self.points[index].force[1] += fy;
+            }
+        }
+    }
+
+    /// Pressure drag and lift on each boundary edge from the air moving past
+    /// it, for air moving at `wind`. Only edges facing into the flow feel it;
+    /// the rest are in their own wake. An open body like a rope traces each
+    /// edge both ways round, so whichever side faces the flow catches it.
+    pub fn apply_aerodynamics(&mut self, wind: [f64; 2]) {
+        let n = self.boundary.len();
+        if (self.drag == 0.0 && self.lift == 0.0) || n < 2 {
+            return;
+        }
+        let side = if geometry::signed_area(&self.outline()) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        for k in 0..n {
+            let (i, j) = (self.boundary[k], self.boundary[(k + 1) % n]);
+            let (a, b) = (self.points[i], self.points[j]);
+            let edge = [b.position[0] - a.position[0], b.position[1] - a.position[1]];
+            let length = edge[0].hypot(edge[1]);
+            if length == 0.0 {
+                continue;
             }
+            let normal = [side * edge[1] / length, -side * edge[0] / length];
+
+            // Velocity of the edge through the air
+            let v = [
+                (a.velocity[0] + b.velocity[0]) / 2.0 - wind[0],
+                (a.velocity[1] + b.velocity[1]) / 2.0 - wind[1],
+            ];
+            let speed = v[0].hypot(v[1]);
+            let approach = v[0] * normal[0] + v[1] * normal[1];
+            if approach <= 0.0 {
+                continue;
+            }
+            let flow = [v[0] / speed, v[1] / speed];
+            let cos = approach / speed;
+
+            // Drag opposes the motion; lift is the rest of the push back
+            // against the face, across the flow
+            let scale = length * approach * speed;
+            let force = [0, 1].map(|axis| {
+                -scale * (self.drag * flow[axis] + self.lift * (normal[axis] - cos * flow[axis]))
+            });
+            for index in [i, j] {
+                self.points[index].force[0] += force[0] / 2.0;
+                self.points[index].force[1] += force[1] / 2.0;
+            }
+        }
+    }
+
+    /// Moves the points with the springs integrated by backward Euler. Every
+    /// other force is taken as it was at the start of the step, and velocities
+    /// aren't clamped.
+    fn step_implicit(&mut self, gravity: [f64; 2], dt: f64) {
+        let jacobians: Vec<SpringJacobian> = self
+            .springs
+            .iter()
+            .filter_map(|spring| {
+                SpringJacobian::new(
+                    spring.point1,
+                    spring.point2,
+                    [
+                        self.points[spring.point1].position,
+                        self.points[spring.point2].position,
+                    ],
+                    spring.stiffness * spring.damping,
+                    spring.rest_length,
+                )
+            })
+            .collect();
+
+        for point in &mut self.points {
+            point.apply_gravity(gravity);
+        }
+        let masses: Vec<f64> = self.points.iter().map(|p| p.mass).collect();
+        let velocities: Vec<[f64; 2]> = self.points.iter().map(|p| p.velocity).collect();
+        let forces: Vec<[f64; 2]> = self.points.iter().map(|p| p.force).collect();
+        let change = implicit::velocity_change(&jacobians, &masses, &velocities, &forces, dt);
+
+        for (point, change) in self.points.iter_mut().zip(change) {
+            point.velocity[0] += change[0];
+            point.velocity[1] += change[1];
+            point.apply_friction(dt);
+            point.update(dt);
         }
     }
 
-    pub fn update(&mut self, window_size: &[f64; 2]) {
+    pub fn update(&mut self, window_size: &[f64; 2], gravity: [f64; 2], wind: [f64; 2], dt: f64) {
         for i in 0..self.springs.len() {
             self.apply_spring_force(i);
         }
+        for i in 0..self.angular_springs.len() {
+            self.apply_angular_spring_force(i);
+        }
+        for i in 0..self.area_constraints.len() {
+            self.apply_area_force(i);
+        }
+        for i in 0..self.elements.len() {
+            self.apply_element_force(i);
+        }
+        self.apply_pressure();
+        self.apply_aerodynamics(wind);
 
-        for point in &mut self.points {
-            point.apply_all();
-            point.update();
+        match self.integrator {
+            Integrator::Explicit => {
+                for point in &mut self.points {
+                    point.apply_all(gravity, dt);
+                    point.update(dt);
+                }
+            }
+            Integrator::Implicit => self.step_implicit(gravity, dt),
         }
 
         for point in &mut self.points {
@@ -306,15 +1079,315 @@ impl SoftBody {
     }
 }
 
+/// Where softbody `body` ends up once softbody `removed` is taken out: gone if
+/// it's the one removed, one place down if it came after it.
+pub fn renumber(body: usize, removed: usize) -> Option<usize> {
+    match body.cmp(&removed) {
+        std::cmp::Ordering::Less => Some(body),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(body - 1),
+    }
+}
+
+/// Pushes apart two points from different softbodies, returning the contact if
+/// they overlap.
+fn point_collision(p1: &mut Point, p2: &mut Point) -> Option<([f64; 2], f64)> {
+    let dx = p2.position[0] - p1.position[0];
+    let dy = p2.position[1] - p1.position[1];
+    let dist_sq = dx * dx + dy * dy;
+    if dist_sq == 0.0 {
+        return None;
+    }
+    let distance = dist_sq.sqrt();
+
+    let radius = (p1.max_velocity + p2.max_velocity) * 2.5;
+    if distance >= radius {
+        return None;
+    }
+
+    let overlap = radius - distance;
+    let force_mag = overlap * 0.5;
+    p1.force[0] -= force_mag * dx / distance;
+    p1.force[1] -= force_mag * dy / distance;
+    p2.force[0] += force_mag * dx / distance;
+    p2.force[1] += force_mag * dy / distance;
+
+    Some(([dx / distance, dy / distance], overlap))
+}
+
+/// A touching pair of points from different softbodies, as (softbody, point)
+/// indices. The normal points from `a` to `b`.
+#[derive(Clone, Copy)]
+pub struct Contact {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+    pub normal: [f64; 2],
+    pub depth: f64,
+}
+
+/// How a joint holds its two points relative to each other.
+#[derive(Clone, Copy, PartialEq)]
+pub enum JointKind {
+    // a rod: the points stay the distance apart they started at
+    Distance,
+    // the points stay together but the bodies turn freely, like an axle
+    Pin,
+    // the points stay together and the bodies keep their angle to each other
+    Weld,
+    // the points can come together but not get further apart than they started
+    Rope,
+}
+
+impl JointKind {
+    pub const ALL: [JointKind; 4] = [
+        JointKind::Distance,
+        JointKind::Pin,
+        JointKind::Weld,
+        JointKind::Rope,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            JointKind::Distance => "Distance",
+            JointKind::Pin => "Pin",
+            JointKind::Weld => "Weld",
+            JointKind::Rope => "Rope",
+        }
+    }
+}
+
+/// A constraint between points of two softbodies, or two points of the same
+/// one, as (softbody, point) indices.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Joint {
+    pub kind: JointKind,
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+    // distance held by a distance joint, and the most a rope lets out
+    pub length: f64,
+    // for welds, the point on each body furthest from the joint, whose
+    // directions from it give the angle the weld holds
+    reference: (usize, usize),
+    rest_angle: f64,
+}
+
+impl Joint {
+    /// Joins `a` and `b` as they are now placed.
+    pub fn new(
+        kind: JointKind,
+        softbodies: &[SoftBody],
+        a: (usize, usize),
+        b: (usize, usize),
+    ) -> Self {
+        let position = |(body, point): (usize, usize)| softbodies[body].points[point].position;
+        let (pa, pb) = (position(a), position(b));
+        let furthest = |(body, point): (usize, usize)| {
+            let anchor = softbodies[body].points[point].position;
+            (0..softbodies[body].points.len())
+                .max_by(|&i, &j| {
+                    let distance = |k: usize| {
+                        let p = softbodies[body].points[k].position;
+                        (p[0] - anchor[0]).hypot(p[1] - anchor[1])
+                    };
+                    distance(i).total_cmp(&distance(j))
+                })
+                .unwrap_or(point)
+        };
+        let mut joint = Joint {
+            kind,
+            a,
+            b,
+            length: (pb[0] - pa[0]).hypot(pb[1] - pa[1]),
+            reference: (furthest(a), furthest(b)),
+            rest_angle: 0.0,
+        };
+        joint.rest_angle = joint.angle(softbodies);
+        joint
+    }
+
+    /// Angle from body `a`'s reference direction around to body `b`'s.
+    fn angle(&self, softbodies: &[SoftBody]) -> f64 {
+        let arm = |(body, point): (usize, usize), reference: usize| {
+            let points = &softbodies[body].points;
+            let (p, r) = (points[point].position, points[reference].position);
+            [r[0] - p[0], r[1] - p[1]]
+        };
+        let (a, b) = (arm(self.a, self.reference.0), arm(self.b, self.reference.1));
+        (a[0] * b[1] - a[1] * b[0]).atan2(a[0] * b[0] + a[1] * b[1])
+    }
+
+    /// Moves the two points back to where the joint allows and takes out the
+    /// velocity that would carry them away again. Corrections are split by
+    /// mass, so a light body does most of the moving.
+    fn hold_points(&self, softbodies: &mut [SoftBody]) {
+        let (pa, pb) = (
+            softbodies[self.a.0].points[self.a.1],
+            softbodies[self.b.0].points[self.b.1],
+        );
+        let d = [
+            pb.position[0] - pa.position[0],
+            pb.position[1] - pa.position[1],
+        ];
+        let v = [
+            pb.velocity[0] - pa.velocity[0],
+            pb.velocity[1] - pa.velocity[1],
+        ];
+        let length = d[0].hypot(d[1]);
+
+        // How far each point has to move, and the relative velocity to remove
+        let (push, slip) = match self.kind {
+            JointKind::Pin | JointKind::Weld => (d, v),
+            JointKind::Distance | JointKind::Rope => {
+                if length == 0.0 || (self.kind == JointKind::Rope && length <= self.length) {
+                    return;
+                }
+                let n = [d[0] / length, d[1] / length];
+                let error = length - self.length;
+                let mut speed = v[0] * n[0] + v[1] * n[1];
+                // a taut rope still lets the ends come together
+                if self.kind == JointKind::Rope {
+                    speed = speed.max(0.0);
+                }
+                ([n[0] * error, n[1] * error], [n[0] * speed, n[1] * speed])
+            }
+        };
+
+        let share_a = pb.mass / (pa.mass + pb.mass);
+        let share_b = 1.0 - share_a;
+        let point_a = &mut softbodies[self.a.0].points[self.a.1];
+        for axis in 0..2 {
+            point_a.position[axis] += push[axis] * share_a;
+            point_a.velocity[axis] += slip[axis] * share_a;
+        }
+        let point_b = &mut softbodies[self.b.0].points[self.b.1];
+        for axis in 0..2 {
+            point_b.position[axis] -= push[axis] * share_b;
+            point_b.velocity[axis] -= slip[axis] * share_b;
+        }
+    }
+
+    /// Turns the two bodies back to the weld's angle and gives them a common
+    /// spin. Each body turns as a whole about its own centre of mass, so the
+    /// weld only ever applies a couple and can't push the pair along.
+    fn hold_angle(&self, softbodies: &mut [SoftBody]) {
+        // Centre of mass and its velocity, moment of inertia about it, and
+        // angular velocity around it
+        let spin = |body: &SoftBody| {
+            let mass: f64 = body.points.iter().map(|p| p.mass).sum();
+            let (mut center, mut drift) = ([0.0, 0.0], [0.0, 0.0]);
+            for p in &body.points {
+                for axis in 0..2 {
+                    center[axis] += p.position[axis] * p.mass / mass;
+                    drift[axis] += p.velocity[axis] * p.mass / mass;
+                }
+            }
+            let (mut inertia, mut momentum) = (0.0, 0.0);
+            for p in &body.points {
+                let r = [p.position[0] - center[0], p.position[1] - center[1]];
+                inertia += p.mass * (r[0] * r[0] + r[1] * r[1]);
+                momentum += p.mass * (r[0] * p.velocity[1] - r[1] * p.velocity[0]);
+            }
+            let spin = if inertia > 0.0 {
+                momentum / inertia
+            } else {
+                0.0
+            };
+            (center, drift, inertia, spin)
+        };
+        let (center_a, drift_a, inertia_a, spin_a) = spin(&softbodies[self.a.0]);
+        let (center_b, drift_b, inertia_b, spin_b) = spin(&softbodies[self.b.0]);
+        let total = inertia_a + inertia_b;
+        if total == 0.0 {
+            return;
+        }
+
+        let error = (self.angle(softbodies) - self.rest_angle + std::f64::consts::PI)
+            .rem_euclid(std::f64::consts::TAU)
+            - std::f64::consts::PI;
+        let common = (inertia_a * spin_a + inertia_b * spin_b) / total;
+        let turns = [
+            (
+                self.a.0,
+                center_a,
+                drift_a,
+                error * inertia_b / total,
+                common - spin_a,
+            ),
+            (
+                self.b.0,
+                center_b,
+                drift_b,
+                -error * inertia_a / total,
+                common - spin_b,
+            ),
+        ];
+        for (body, center, drift, angle, spin_change) in turns {
+            let (sin, cos) = angle.sin_cos();
+            for p in &mut softbodies[body].points {
+                let r = [p.position[0] - center[0], p.position[1] - center[1]];
+                p.position = [
+                    center[0] + r[0] * cos - r[1] * sin,
+                    center[1] + r[0] * sin + r[1] * cos,
+                ];
+                // Only the motion about the centre turns; the body keeps its heading
+                let v = [p.velocity[0] - drift[0], p.velocity[1] - drift[1]];
+                p.velocity = [
+                    drift[0] + v[0] * cos - v[1] * sin - spin_change * r[1],
+                    drift[1] + v[0] * sin + v[1] * cos + spin_change * r[0],
+                ];
+            }
+        }
+    }
+
+    pub fn solve(&self, softbodies: &mut [SoftBody]) {
+        // Turning the bodies moves the joined points apart, so they're brought
+        // back together afterwards
+        if self.kind == JointKind::Weld && self.a.0 != self.b.0 {
+            self.hold_angle(softbodies);
+        }
+        self.hold_points(softbodies);
+    }
+}
+
 #[derive(Clone)]
 pub struct SoftBodyCollection {
     pub softbodies: Vec<SoftBody>,
+    pub gravity: [f64; 2],
+    // velocity of the air, felt by bodies with aerodynamic edges
+    pub wind: [f64; 2],
+    // contacts and broadphase from the last update, for the debug overlay
+    pub contacts: Vec<Contact>,
+    pub grid: SpatialGrid,
+    // static geometry the softbodies collide with but never move
+    pub colliders: Vec<Collider>,
+    pub rigid_bodies: Vec<RigidBody>,
+    // constraints between points of different softbodies
+    pub joints: Vec<Joint>,
+    // forces acting on top of gravity, each in its own region
+    pub fields: Vec<Field>,
+    // liquid that buoys up and slows down whatever is in it
+    pub fluids: Vec<Fluid>,
+    // frames simulated since the scene started, for fields that change over time
+    time: f64,
+    // rigid contacts from the last update, to warm-start the next
+    rigid_contacts: Vec<rigid::Contact>,
 }
 
 impl SoftBodyCollection {
     pub fn new() -> Self {
         SoftBodyCollection {
             softbodies: Vec::new(),
+            gravity: DEFAULT_GRAVITY,
+            wind: [0.0, 0.0],
+            contacts: Vec::new(),
+            grid: SpatialGrid::new(),
+            colliders: Vec::new(),
+            rigid_bodies: Vec::new(),
+            joints: Vec::new(),
+            fields: Vec::new(),
+            fluids: Vec::new(),
+            time: 0.0,
+            rigid_contacts: Vec::new(),
         }
     }
 
@@ -322,22 +1395,621 @@ impl SoftBodyCollection {
         self.softbodies.push(softbody);
     }
 
+    /// Removes a softbody along with its joints, renumbering the joints on the
+    /// bodies after it. The contacts from the last step point into the old
+    /// numbering, so they go too.
+    pub fn remove(&mut self, index: usize) {
+        self.softbodies.remove(index);
+        self.contacts.clear();
+        self.joints.retain_mut(|joint| {
+            match (renumber(joint.a.0, index), renumber(joint.b.0, index)) {
+                (Some(a), Some(b)) => {
+                    (joint.a.0, joint.b.0) = (a, b);
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+
+    pub fn add_joint(&mut self, joint: Joint) {
+        self.joints.push(joint);
+    }
+
+    pub fn add_field(&mut self, field: Field) {
+        self.fields.push(field);
+    }
+
+    /// Adds each field's force to the softbody points and rigid bodies inside it.
+    fn apply_fields(&mut self, dt: f64) {
+        let time = self.time;
+        for point in self.softbodies.iter_mut().flat_map(|sb| &mut sb.points) {
+            for field in &self.fields {
+                let force = field.force_on(point.position, point.velocity, point.mass, time);
+                point.force[0] += force[0];
+                point.force[1] += force[1];
+            }
+        }
+        for body in self.rigid_bodies.iter_mut().filter(|b| !b.is_static()) {
+            for field in &self.fields {
+                let force = field.force_on(body.position, body.velocity, body.mass(), time);
+                body.apply_impulse([force[0] * dt, force[1] * dt], body.position);
+            }
+        }
+    }
+
+    pub fn add_fluid(&mut self, fluid: Fluid) {
+        self.fluids.push(fluid);
+    }
+
+    /// Buoyancy and drag from the fluids. A body is buoyed up by the weight of
+    /// the fluid its submerged part displaces, measured by clipping its outline
+    /// to the fluid. Softbodies share that out over their submerged points by
+    /// mass, so a body floats when it's lighter than the fluid it displaces;
+    /// rigid bodies take it at the submerged centroid, which rights them.
+    fn apply_fluids(&mut self, dt: f64) {
+        let gravity = self.gravity;
+        for fluid in &self.fluids {
+            for softbody in &mut self.softbodies {
+                let wet: Vec<usize> = (0..softbody.points.len())
+                    .filter(|&i| fluid.contains(softbody.points[i].position))
+                    .collect();
+                if wet.is_empty() {
+                    continue;
+                }
+                // Holes displace nothing, so their wet part comes off the area
+                let area = |outline: &[[f64; 2]]| fluid.submerged(outline).map_or(0.0, |s| s.0);
+                let holes: f64 = softbody
+                    .holes
+                    .iter()
+                    .map(|hole| {
+                        let outline: Vec<[f64; 2]> =
+                            hole.iter().map(|&i| softbody.points[i].position).collect();
+                        area(&outline)
+                    })
+                    .sum();
+                let displaced = fluid.density * (area(&softbody.outline()) - holes).max(0.0);
+                let wet_mass: f64 = wet.iter().map(|&i| softbody.points[i].mass).sum();
+                for i in wet {
+                    let point = &mut softbody.points[i];
+                    let lift = displaced * point.mass / wet_mass;
+                    let drag = fluid.viscosity * point.mass;
+                    point.force[0] -= gravity[0] * lift + drag * point.velocity[0];
+                    point.force[1] -= gravity[1] * lift + drag * point.velocity[1];
+                }
+            }
+
+            for body in self.rigid_bodies.iter_mut().filter(|b| !b.is_static()) {
+                let Some((area, center)) = fluid.submerged(&body.outline()) else {
+                    continue;
+                };
+                let buoyancy = fluid.density * area * dt;
+                body.apply_impulse([-gravity[0] * buoyancy, -gravity[1] * buoyancy], center);
+                let drag = fluid.viscosity * dt * area / body.area();
+                let momentum = body.mass() * drag;
+                let position = body.position;
+                body.apply_impulse(
+                    [-body.velocity[0] * momentum, -body.velocity[1] * momentum],
+                    position,
+                );
+                body.angular_velocity *= 1.0 - drag;
+            }
+        }
+    }
+
+    pub fn add_rigid(&mut self, body: RigidBody) {
+        self.rigid_bodies.push(body);
+    }
+
+    pub fn remove_rigid(&mut self, index: usize) {
+        self.rigid_bodies.remove(index);
+        // the cached contacts refer to bodies by index
+        self.rigid_contacts.clear();
+    }
+
+    /// Pushes softbody points out of the rigid bodies they've moved into.
+    pub fn rigid_collisions(&mut self) {
+        for softbody in &mut self.softbodies {
+            let [min_x, min_y, max_x, max_y] = softbody.aabb();
+            for body in &mut self.rigid_bodies {
+                let aabb = body.aabb();
+                if aabb[0] > max_x || aabb[2] < min_x || aabb[1] > max_y || aabb[3] < min_y {
+                    continue;
+                }
+                for point in &mut softbody.points {
+                    point.handle_rigid_collision(body);
+                }
+            }
+        }
+    }
+
     pub fn softbody_collisions(&mut self) {
-        let len = self.softbodies.len();
-        for i in 0..len {
-            for j in (i + 1)..len {
-                let (left, right) = self.softbodies.split_at_mut(j);
-                let a = &mut left[i];
-                let b = &mut right[0];
-                a.softbody_collision(b);
+        // Cells as wide as the largest contact radius mean every overlapping pair
+        // is in the same or a neighbouring cell
+        let max_velocity = self
+            .softbodies
+            .iter()
+            .flat_map(|sb| &sb.points)
+            .fold(0.0, |max: f64, p| max.max(p.max_velocity));
+        let cell_size = (max_velocity * 5.0).max(1.0);
+
+        self.grid.rebuild(
+            cell_size,
+            self.softbodies.iter().enumerate().flat_map(|(sbi, sb)| {
+                sb.points
+                    .iter()
+                    .enumerate()
+                    .map(move |(pi, p)| (sbi, pi, p.position))
+            }),
+        );
+
+        // Jointed bodies overlap where they're joined, so they pass through
+        // each other rather than fight the joint
+        let joined: Vec<(usize, usize)> = self
+            .joints
+            .iter()
+            .map(|joint| (joint.a.0.min(joint.b.0), joint.a.0.max(joint.b.0)))
+            .collect();
+
+        self.contacts.clear();
+        let softbodies = &mut self.softbodies;
+        let contacts = &mut self.contacts;
+        self.grid.for_each_pair(|a, b| {
+            let (a, b) = if a.0 < b.0 { (a, b) } else { (b, a) };
+            if joined.contains(&(a.0, b.0)) {
+                return;
+            }
+            let (left, right) = softbodies.split_at_mut(b.0);
+            let p1 = &mut left[a.0].points[a.1];
+            let p2 = &mut right[0].points[b.1];
+            if let Some((normal, depth)) = point_collision(p1, p2) {
+                contacts.push(Contact {
+                    a,
+                    b,
+                    normal,
+                    depth,
+                });
             }
+        });
+    }
+
+    pub fn set_stiffness(&mut self, stiffness: f64) {
+        for spring in self.softbodies.iter_mut().flat_map(|sb| &mut sb.springs) {
+            spring.stiffness = stiffness;
         }
     }
 
-    pub fn update(&mut self, window_size: &[f64; 2]) {
+    pub fn set_damping(&mut self, damping: f64) {
+        for spring in self.softbodies.iter_mut().flat_map(|sb| &mut sb.springs) {
+            spring.damping = damping;
+        }
+    }
+
+    pub fn set_plasticity(&mut self, yield_strain: f64, creep: f64) {
+        for spring in self.softbodies.iter_mut().flat_map(|sb| &mut sb.springs) {
+            spring.yield_strain = yield_strain;
+            spring.creep = creep;
+        }
+    }
+
+    pub fn set_area_stiffness(&mut self, stiffness: f64) {
+        for constraint in self
+            .softbodies
+            .iter_mut()
+            .flat_map(|sb| &mut sb.area_constraints)
+        {
+            constraint.stiffness = stiffness;
+        }
+    }
+
+    /// Sets the friction of every point, except on bodies whose air
+    /// resistance comes from their edges.
+    pub fn set_friction(&mut self, friction: f64) {
         for softbody in &mut self.softbodies {
-            softbody.update(window_size);
+            if softbody.drag == 0.0 && softbody.lift == 0.0 {
+                for point in &mut softbody.points {
+                    point.friction = friction;
+                }
+            }
         }
+    }
+
+    /// Advances the simulation by `dt` steps, where 1.0 is one frame at normal speed.
+    pub fn update(&mut self, window_size: &[f64; 2], dt: f64) {
+        self.apply_fields(dt);
+        self.apply_fluids(dt);
+        self.time += dt;
+        for softbody in &mut self.softbodies {
+            let gravity = self.gravity.map(|g| g * softbody.gravity_scale);
+            softbody.update(window_size, gravity, self.wind, dt);
+            for point in &mut softbody.points {
+                for collider in &self.colliders {
+                    point.handle_collider_collision(collider);
+                }
+            }
+        }
+        for joint in &self.joints {
+            joint.solve(&mut self.softbodies);
+        }
+        rigid::step(
+            &mut self.rigid_bodies,
+            &mut self.rigid_contacts,
+            &self.colliders,
+            *window_size,
+            self.gravity,
+            dt,
+        );
+        self.rigid_collisions();
         self.softbody_collisions();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Uniform;
+    use crate::fluid::DEFAULT_DENSITY;
+    use std::f64::consts::{PI, TAU};
+
+    #[test]
+    fn bodies_of_uneven_mass_fall_without_warping() {
+        let material = &MATERIALS[1];
+        let mut body = SoftBody::new_regular_polygon([500.0, 100.0], 50.0, 8)
+            .with_material(material)
+            .with_fem(material);
+        let masses: Vec<f64> = body.points.iter().map(|p| p.mass).collect();
+        assert!(masses.iter().any(|&m| m != masses[0]));
+
+        for _ in 0..20 {
+            body.update(&[1000.0, 10000.0], DEFAULT_GRAVITY, [0.0, 0.0], 1.0);
+        }
+        let vy = body.points[0].velocity[1];
+        assert!(vy > 0.0);
+        for point in &body.points {
+            assert!((point.velocity[1] - vy).abs() < 1e-6 * vy);
+        }
+    }
+
+    #[test]
+    fn spring_and_fem_bodies_fall_at_the_same_rate() {
+        let material = &MATERIALS[1];
+        let mut springy =
+            SoftBody::new_regular_polygon([200.0, 100.0], 50.0, 8).with_material(material);
+        let mut fem = SoftBody::new_regular_polygon([600.0, 100.0], 30.0, 8)
+            .with_material(material)
+            .with_fem(material);
+        let mass = |body: &SoftBody| body.points.iter().map(|p| p.mass).sum::<f64>();
+        assert!((mass(&springy) - mass(&fem)).abs() > 0.1 * mass(&springy));
+
+        for _ in 0..20 {
+            springy.update(&[1000.0, 10000.0], DEFAULT_GRAVITY, [0.0, 0.0], 1.0);
+            fem.update(&[1000.0, 10000.0], DEFAULT_GRAVITY, [0.0, 0.0], 1.0);
+        }
+        // Velocity of each body's centre of mass
+        let fall = |body: &SoftBody| {
+            body.points
+                .iter()
+                .map(|p| p.velocity[1] * p.mass)
+                .sum::<f64>()
+                / mass(body)
+        };
+        assert!(fall(&springy) > 0.0);
+        assert!((fall(&springy) - fall(&fem)).abs() < 1e-6 * fall(&springy));
+    }
+
+    /// Total upward force the fluids put on each softbody at rest.
+    fn lift(collection: &mut SoftBodyCollection) -> Vec<f64> {
+        collection.apply_fluids(1.0);
+        collection
+            .softbodies
+            .iter()
+            .map(|body| -body.points.iter().map(|p| p.force[1]).sum::<f64>())
+            .collect()
+    }
+
+    #[test]
+    fn buoyancy_is_the_weight_of_the_fluid_displaced() {
+        let mut collection = SoftBodyCollection::new();
+        collection.add_fluid(Fluid::new_rect([0.0, 0.0, 1000.0, 1000.0]));
+        // Same size, different numbers of points
+        collection.add(SoftBody::new_square([100.0, 100.0], 100.0, 2));
+        collection.add(SoftBody::new_square([400.0, 100.0], 100.0, 8));
+        let weight = DEFAULT_GRAVITY[1] * DEFAULT_DENSITY * 100.0 * 100.0;
+        for lift in lift(&mut collection) {
+            assert!((lift - weight).abs() < 1e-9 * weight);
+        }
+    }
+
+    #[test]
+    fn holes_displace_no_fluid() {
+        let mut collection = SoftBodyCollection::new();
+        collection.add_fluid(Fluid::new_rect([0.0, 0.0, 1000.0, 1000.0]));
+        let ring = SoftBody::new_ring([500.0, 500.0], 60.0, 100.0, 16);
+        let solid = geometry::signed_area(&ring.outline()).abs();
+        let hole: Vec<[f64; 2]> = ring.holes[0]
+            .iter()
+            .map(|&i| ring.points[i].position)
+            .collect();
+        let area = solid - geometry::signed_area(&hole).abs();
+        collection.add(ring);
+        let weight = DEFAULT_GRAVITY[1] * DEFAULT_DENSITY * area;
+        assert!((lift(&mut collection)[0] - weight).abs() < 1e-9 * weight);
+    }
+
+    #[test]
+    fn removing_a_body_drops_its_contacts() {
+        let mut collection = SoftBodyCollection::new();
+        collection.add(SoftBody::new_square([100.0, 100.0], 50.0, 2));
+        collection.add(SoftBody::new_square([149.0, 100.0], 50.0, 2));
+        collection.update(&[1000.0, 1000.0], 1.0);
+        assert!(!collection.contacts.is_empty());
+        collection.remove(1);
+        assert!(collection.contacts.is_empty());
+    }
+
+    #[test]
+    fn ring_points_start_out_of_each_others_reach() {
+        // A thick ring, where the inner rim is much more crowded than the outer
+        let ring = SoftBody::new_ring([0.0, 0.0], 60.0, 100.0, 24);
+        for (i, p) in ring.points.iter().enumerate() {
+            for q in &ring.points[i + 1..] {
+                let distance = (p.position[0] - q.position[0]).hypot(p.position[1] - q.position[1]);
+                assert!(distance > p.max_velocity * 3.0);
+            }
+        }
+    }
+
+    #[test]
+    fn small_polygons_have_one_spring_per_pair() {
+        for sides in 3..=6 {
+            let body = SoftBody::new_regular_polygon([0.0, 0.0], 50.0, sides);
+            let mut pairs: Vec<(usize, usize)> = body
+                .springs
+                .iter()
+                .map(|s| (s.point1.min(s.point2), s.point1.max(s.point2)))
+                .collect();
+            let count = pairs.len();
+            pairs.sort_unstable();
+            pairs.dedup();
+            assert_eq!(pairs.len(), count, "{} sides", sides);
+        }
+    }
+
+    #[test]
+    fn removing_a_body_renumbers_the_joints_after_it() {
+        let mut collection = SoftBodyCollection::new();
+        for x in [0.0, 200.0, 400.0] {
+            collection.add(SoftBody::new_square([x, 100.0], 50.0, 2));
+        }
+        let bodies = &collection.softbodies;
+        let joints = [
+            Joint::new(JointKind::Distance, bodies, (0, 0), (1, 0)),
+            Joint::new(JointKind::Pin, bodies, (0, 1), (2, 1)),
+        ];
+        for joint in joints {
+            collection.add_joint(joint);
+        }
+        collection.remove(1);
+        assert_eq!(collection.joints.len(), 1);
+        assert_eq!(
+            (collection.joints[0].a, collection.joints[0].b),
+            ((0, 1), (1, 1))
+        );
+    }
+
+    #[test]
+    fn bent_ropes_straighten() {
+        let mut rope = SoftBody::new_rope([400.0, 500.0], [600.0, 500.0], 2);
+        rope.points[2].position[1] -= 60.0;
+        let bend = |rope: &SoftBody| {
+            let spring = &rope.angular_springs[0];
+            let turn = spring.angle(&rope.points) - spring.rest_angle;
+            ((turn + PI).rem_euclid(TAU) - PI).abs()
+        };
+        let start = bend(&rope);
+        for _ in 0..200 {
+            rope.update(&[1000.0, 1000.0], [0.0, 0.0], [0.0, 0.0], 1.0);
+        }
+        assert!(bend(&rope) < start * 0.2, "{} -> {}", start, bend(&rope));
+    }
+
+    #[test]
+    fn area_forces_push_squashed_and_flipped_triangles_back() {
+        let mut body = SoftBody::new();
+        for position in [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]] {
+            body.points.push(Point::new(position, 1.0, 1.0));
+        }
+        body.triangles.push([0, 1, 2]);
+        body.constrain_areas();
+        let rest = body.area_constraints[0].rest_area;
+
+        // Squashed toward the opposite edge, then pushed through it
+        for height in [4.0, -4.0] {
+            body.points[2].position = [0.0, height];
+            for point in &mut body.points {
+                point.force = [0.0, 0.0];
+            }
+            body.apply_area_force(0);
+            let before = body.area_constraints[0].area(&body.points);
+            let mut moved = body.points.clone();
+            for point in &mut moved {
+                point.position[0] += point.force[0] * 0.01;
+                point.position[1] += point.force[1] * 0.01;
+            }
+            let after = body.area_constraints[0].area(&moved);
+            assert!((after - rest).abs() < (before - rest).abs());
+        }
+    }
+
+    /// Two squares side by side, joined from the right corner of the first to
+    /// the left corner of the second with `kind`.
+    fn joined(kind: JointKind) -> (Vec<SoftBody>, Joint) {
+        let bodies = vec![
+            SoftBody::new_square([100.0, 100.0], 40.0, 1),
+            SoftBody::new_square([160.0, 100.0], 40.0, 1),
+        ];
+        // Corners are numbered down each column, so 2 is top right and 0 top left
+        let joint = Joint::new(kind, &bodies, (0, 2), (1, 0));
+        (bodies, joint)
+    }
+
+    fn gap(bodies: &[SoftBody], joint: &Joint) -> f64 {
+        let (a, b) = (
+            bodies[joint.a.0].points[joint.a.1].position,
+            bodies[joint.b.0].points[joint.b.1].position,
+        );
+        (b[0] - a[0]).hypot(b[1] - a[1])
+    }
+
+    #[test]
+    fn joints_hold_their_points() {
+        for kind in [JointKind::Distance, JointKind::Rope, JointKind::Pin] {
+            let (mut bodies, joint) = joined(kind);
+            bodies[1].points[0].position = [240.0, 130.0];
+            joint.solve(&mut bodies);
+            let expected = if kind == JointKind::Pin { 0.0 } else { 20.0 };
+            assert!(
+                (gap(&bodies, &joint) - expected).abs() < 1e-9,
+                "{}",
+                kind.label()
+            );
+        }
+
+        // A rope only pulls; it goes slack when the ends come together
+        let (mut bodies, joint) = joined(JointKind::Rope);
+        bodies[1].points[0].position = [145.0, 100.0];
+        joint.solve(&mut bodies);
+        assert!((gap(&bodies, &joint) - 5.0).abs() < 1e-9);
+    }
+
+    fn momentum(bodies: &[SoftBody]) -> [f64; 2] {
+        bodies
+            .iter()
+            .flat_map(|b| &b.points)
+            .fold([0.0, 0.0], |m, p| {
+                [m[0] + p.mass * p.velocity[0], m[1] + p.mass * p.velocity[1]]
+            })
+    }
+
+    #[test]
+    fn welds_keep_the_bodies_angle() {
+        let (bodies, joint) = joined(JointKind::Weld);
+        let mut collection = SoftBodyCollection::new();
+        collection.gravity = [0.0, 0.0];
+        for mut body in bodies {
+            body.points.iter_mut().for_each(|p| p.position[1] += 300.0);
+            collection.add(body);
+        }
+        collection.add_joint(joint);
+        // Spin the second body about its middle
+        for point in &mut collection.softbodies[1].points {
+            let d = [point.position[0] - 180.0, point.position[1] - 420.0];
+            point.velocity = [-d[1] * 0.02, d[0] * 0.02];
+        }
+        for _ in 0..100 {
+            collection.update(&[1000.0, 1000.0], 1.0);
+        }
+
+        let joint = &collection.joints[0];
+        let turn = joint.angle(&collection.softbodies) - joint.rest_angle;
+        assert!(turn.abs() < 0.05, "{}", turn);
+        assert!(gap(&collection.softbodies, joint) < 1.0);
+    }
+
+    #[test]
+    fn welds_do_not_push_the_pair_along() {
+        let (mut bodies, joint) = joined(JointKind::Weld);
+        // Both bodies moving and one spinning, so the weld has to turn them
+        for point in &mut bodies[0].points {
+            point.velocity = [1.0, -0.5];
+        }
+        for point in &mut bodies[1].points {
+            let d = [point.position[0] - 180.0, point.position[1] - 120.0];
+            point.velocity = [-0.3 - d[1] * 0.05, 0.8 + d[0] * 0.05];
+        }
+        let before = momentum(&bodies);
+        for point in bodies.iter_mut().flat_map(|b| &mut b.points) {
+            point.position[0] += point.velocity[0];
+            point.position[1] += point.velocity[1];
+        }
+        joint.solve(&mut bodies);
+        let after = momentum(&bodies);
+        assert!((after[0] - before[0]).abs() < 1e-9 && (after[1] - before[1]).abs() < 1e-9);
+    }
+
+    /// Net air force on `body` moving at `velocity` through still air.
+    fn air_force(body: &SoftBody, velocity: [f64; 2]) -> [f64; 2] {
+        let mut body = body.clone();
+        for point in &mut body.points {
+            point.velocity = velocity;
+            point.force = [0.0, 0.0];
+        }
+        body.apply_aerodynamics([0.0, 0.0]);
+        body.points
+            .iter()
+            .fold([0.0, 0.0], |f, p| [f[0] + p.force[0], f[1] + p.force[1]])
+    }
+
+    #[test]
+    fn drag_opposes_motion_and_grows_with_speed_squared() {
+        let square = SoftBody::new_square([0.0, 0.0], 50.0, 2).with_aerodynamics(0.01, 0.0);
+        let slow = air_force(&square, [2.0, 0.0]);
+        let fast = air_force(&square, [4.0, 0.0]);
+        assert!(slow[0] < 0.0 && slow[1].abs() < 1e-12);
+        assert!((fast[0] - 4.0 * slow[0]).abs() < 1e-9);
+        // Moving with the wind, there's nothing to push against
+        let mut square = square;
+        square
+            .points
+            .iter_mut()
+            .for_each(|p| p.velocity = [3.0, 0.0]);
+        square.apply_aerodynamics([3.0, 0.0]);
+        assert!(square.points.iter().all(|p| p.force == [0.0, 0.0]));
+    }
+
+    #[test]
+    fn tilted_plates_get_lift_across_the_flow() {
+        // Leading edge raised, moving right: the air pushes it up as well as back
+        let plate = SoftBody::new_rope([0.0, 0.0], [100.0, -20.0], 4);
+        let drag_only = air_force(&plate.clone().with_aerodynamics(0.01, 0.0), [5.0, 0.0]);
+        let lifted = air_force(&plate.with_aerodynamics(0.01, 0.02), [5.0, 0.0]);
+        assert!(drag_only[0] < 0.0 && drag_only[1].abs() < 1e-12);
+        assert!(lifted[1] < 0.0);
+        assert!((lifted[0] - drag_only[0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gravity_turns_and_scales_per_body() {
+        let mut collection = SoftBodyCollection::new();
+        collection.gravity = [0.1, 0.0];
+        for (x, scale) in [(100.0, 1.0), (300.0, -1.0), (500.0, 0.5)] {
+            collection.add(SoftBody::new_square([x, 400.0], 50.0, 2).with_gravity_scale(scale));
+        }
+        collection
+            .add_rigid(RigidBody::new_box([700.0, 400.0], 50.0, 50.0).with_gravity_scale(-1.0));
+        collection.update(&[1000.0, 1000.0], 1.0);
+
+        let velocity = |body: usize| collection.softbodies[body].points[0].velocity;
+        let sideways = velocity(0)[0];
+        assert!(sideways > 0.0 && velocity(0)[1].abs() < 1e-12);
+        assert!((velocity(1)[0] + sideways).abs() < 1e-12);
+        assert!((velocity(2)[0] - sideways / 2.0).abs() < 1e-12);
+        assert!(collection.rigid_bodies[0].velocity[0] < 0.0);
+    }
+
+    #[test]
+    fn static_rigid_bodies_ignore_fields_and_fluids() {
+        let mut collection = SoftBodyCollection::new();
+        let ledge = RigidBody::new_box([500.0, 500.0], 200.0, 20.0).with_static();
+        collection.add_rigid(ledge.clone());
+        collection.add_fluid(Fluid::new_rect([300.0, 400.0, 700.0, 600.0]));
+        collection.add_field(Field::new(Uniform {
+            acceleration: [0.05, 0.0],
+        }));
+        collection.add(SoftBody::new_square([475.0, 440.0], 50.0, 2));
+        for _ in 0..20 {
+            collection.update(&[1000.0, 1000.0], 1.0);
+        }
+        assert!(collection.rigid_bodies[0] == ledge);
+    }
+}
@@ -0,0 +1,98 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+// Triple buffer: the writer fills its back slot, then swaps it with the shared
+// middle slot; the reader swaps the middle slot into its front slot whenever a
+// fresh one is waiting. Neither side ever waits on the other for longer than a swap.
+struct Shared<T> {
+    slot: T,
+    fresh: bool,
+}
+
+pub struct SnapshotWriter<T> {
+    back: T,
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+pub struct SnapshotReader<T> {
+    front: T,
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+pub fn channel<T: Clone>(initial: &T) -> (SnapshotWriter<T>, SnapshotReader<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        slot: initial.clone(),
+        fresh: false,
+    }));
+
+    (
+        SnapshotWriter {
+            back: initial.clone(),
+            shared: Arc::clone(&shared),
+        },
+        SnapshotReader {
+            front: initial.clone(),
+            shared,
+        },
+    )
+}
+
+impl<T: Clone> SnapshotWriter<T> {
+    pub fn publish(&mut self, state: &T) {
+        self.back.clone_from(state);
+
+        let mut shared = self.shared.lock().unwrap();
+        mem::swap(&mut shared.slot, &mut self.back);
+        shared.fresh = true;
+    }
+}
+
+impl<T> SnapshotReader<T> {
+    /// Pulls in the most recently published snapshot, returning true if it is new.
+    pub fn update(&mut self) -> bool {
+        let mut shared = self.shared.lock().unwrap();
+        if !shared.fresh {
+            return false;
+        }
+        mem::swap(&mut shared.slot, &mut self.front);
+        shared.fresh = false;
+        true
+    }
+
+    pub fn latest(&self) -> &T {
+        &self.front
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_sees_only_the_latest_snapshot_once() {
+        let (mut writer, mut reader) = channel(&0);
+        assert!(!reader.update());
+        assert_eq!(*reader.latest(), 0);
+
+        writer.publish(&1);
+        writer.publish(&2);
+        assert!(reader.update());
+        assert_eq!(*reader.latest(), 2);
+        assert!(!reader.update());
+        assert_eq!(*reader.latest(), 2);
+    }
+
+    #[test]
+    fn snapshots_cross_threads() {
+        let (mut writer, mut reader) = channel(&Vec::new());
+        std::thread::spawn(move || {
+            for n in 1..=100 {
+                writer.publish(&vec![n; n]);
+            }
+        })
+        .join()
+        .unwrap();
+        assert!(reader.update());
+        assert_eq!(reader.latest(), &vec![100; 100]);
+    }
+}
@@ -0,0 +1,912 @@
+// Loads scenes drawn in an SVG editor. Closed shapes become meshed softbodies,
+// shapes inside the designated static layer become static colliders, and
+// convex shapes in the fluid layer become tanks of water, with `data-density`,
+// `data-viscosity` and `data-level` attributes to tune them. Only the
+// parts of SVG that level drawings use are understood: groups, transforms,
+// paths, rects, circles, ellipses and polygons. A shape that can't be meshed,
+// or a concave tank, is left out and reported rather than failing the whole
+// scene.
+
+use std::fs;
+
+use crate::collider::Collider;
+use crate::fluid::Fluid;
+use crate::geometry;
+use crate::mesh::Mesh;
+use crate::simulation::{SoftBody, SoftBodyCollection};
+
+// x' = a x + c y + e, y' = b x + d y + f
+type Transform = [f64; 6];
+
+const IDENTITY: Transform = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// How SVG drawings map onto the world.
+pub struct SvgOptions {
+    // world units per SVG unit
+    pub scale: f64,
+    // largest distance a flattened curve may stray from the real one, in world units
+    pub tolerance: f64,
+    // target spacing between mesh points of imported bodies, in world units
+    pub spacing: f64,
+    // label or id of the group whose shapes become static colliders
+    pub static_layer: String,
+    // label or id of the group whose shapes are filled with water
+    pub fluid_layer: String,
+}
+
+/// What the shapes in a group turn into.
+#[derive(Clone, Copy, PartialEq)]
+enum Layer {
+    Bodies,
+    Static,
+    Fluid,
+}
+
+/// One closed shape with any shapes nested inside it cut out as holes.
+struct Shape {
+    outline: Vec<[f64; 2]>,
+    holes: Vec<Vec<[f64; 2]>>,
+    color: Option<[f32; 4]>,
+}
+
+/// Reads an SVG file into a scene of softbodies, colliders and fluids, along
+/// with a message for each shape that was left out.
+pub fn load(path: &str, options: &SvgOptions) -> Result<(SoftBodyCollection, Vec<String>), String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("could not read svg '{}': {}", path, e))?;
+    parse(&contents, options)
+}
+
+pub fn parse(svg: &str, options: &SvgOptions) -> Result<(SoftBodyCollection, Vec<String>), String> {
+    let mut collection = SoftBodyCollection::new();
+    let mut skipped = Vec::new();
+    // (transform, layer) for each open group
+    let mut groups: Vec<(Transform, Layer)> = Vec::new();
+    // how many elements deep we are inside `<defs>` or a hidden element
+    let mut hidden = 0;
+
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        // Comments and declarations may contain '>' of their own
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or("unterminated comment in svg")?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated tag in svg")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(closing) = tag.strip_prefix('/') {
+            if hidden > 0 {
+                hidden -= 1;
+            } else if closing.trim() == "g" {
+                groups.pop();
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name = tag.split_whitespace().next().unwrap_or("");
+        let attributes = attributes(tag);
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+
+        // Nothing under definitions or hidden layers is drawn, so none of it
+        // is part of the scene
+        if hidden > 0 {
+            if !self_closing {
+                hidden += 1;
+            }
+            continue;
+        }
+        let display = attribute("display").or_else(|| style_value(attribute("style")?, "display"));
+        if name == "defs" || display == Some("none") {
+            if !self_closing {
+                hidden = 1;
+            }
+            continue;
+        }
+
+        let parent = groups.last().copied().unwrap_or((
+            [options.scale, 0.0, 0.0, options.scale, 0.0, 0.0],
+            Layer::Bodies,
+        ));
+        let transform = match attribute("transform") {
+            Some(t) => compose(parent.0, parse_transform(t)?),
+            None => parent.0,
+        };
+
+        if name == "g" {
+            if !self_closing {
+                // Groups nested in a layer stay part of it
+                let labelled = |layer: &str| {
+                    [attribute("inkscape:label"), attribute("id")]
+                        .into_iter()
+                        .flatten()
+                        .any(|label| label.eq_ignore_ascii_case(layer))
+                };
+                let layer = if parent.1 != Layer::Bodies {
+                    parent.1
+                } else if labelled(&options.static_layer) {
+                    Layer::Static
+                } else if labelled(&options.fluid_layer) {
+                    Layer::Fluid
+                } else {
+                    Layer::Bodies
+                };
+                groups.push((transform, layer));
+            }
+            continue;
+        }
+
+        let number = |key: &str| -> Result<f64, String> {
+            attribute(key).map_or(Ok(0.0), |v| {
+                v.trim_end_matches("px")
+                    .parse()
+                    .map_err(|_| format!("invalid {} '{}' in svg", key, v))
+            })
+        };
+        // Sizes have no sensible default; without one the shape collapses
+        let size = |key: &str| -> Result<f64, String> {
+            match attribute(key) {
+                Some(_) => number(key).and_then(|v| {
+                    (v > 0.0)
+                        .then_some(v)
+                        .ok_or_else(|| format!("{} of <{}> must be positive in svg", key, name))
+                }),
+                None => Err(format!("<{}> is missing its {} in svg", name, key)),
+            }
+        };
+        // Curves are flattened in SVG units, so scale the tolerance to match
+        let tolerance = options.tolerance / scale_of(transform).max(f64::EPSILON);
+        let rings = match name {
+            "path" => parse_path(attribute("d").unwrap_or(""), tolerance)?,
+            "polygon" => {
+                let mut ring: Vec<[f64; 2]> = parse_numbers(attribute("points").unwrap_or(""))?
+                    .chunks_exact(2)
+                    .map(|p| [p[0], p[1]])
+                    .collect();
+                // Some editors repeat the first point to close the outline
+                if ring.len() > 1 && ring.first() == ring.last() {
+                    ring.pop();
+                }
+                vec![ring]
+            }
+            "rect" => {
+                let (x, y) = (number("x")?, number("y")?);
+                let (w, h) = (size("width")?, size("height")?);
+                vec![vec![[x, y], [x + w, y], [x + w, y + h], [x, y + h]]]
+            }
+            "circle" => {
+                let r = size("r")?;
+                vec![ellipse(number("cx")?, number("cy")?, r, r, tolerance)]
+            }
+            "ellipse" => vec![ellipse(
+                number("cx")?,
+                number("cy")?,
+                size("rx")?,
+                size("ry")?,
+                tolerance,
+            )],
+            _ => continue,
+        };
+
+        let rings: Vec<Vec<[f64; 2]>> = rings
+            .into_iter()
+            .filter(|ring| ring.len() >= 3)
+            .map(|ring| ring.into_iter().map(|p| apply(transform, p)).collect())
+            .collect();
+
+        match parent.1 {
+            Layer::Static => {
+                for ring in rings {
+                    collection.colliders.push(Collider::new(ring));
+                }
+            }
+            // Tanks must be convex, so concave shapes are reported and left out
+            Layer::Fluid => {
+                let setting = |key: &str| -> Result<Option<f64>, String> {
+                    attribute(key)
+                        .map(|v| {
+                            v.parse()
+                                .map_err(|_| format!("invalid {} '{}' in svg", key, v))
+                        })
+                        .transpose()
+                };
+                let (density, viscosity) = (setting("data-density")?, setting("data-viscosity")?);
+                let level = setting("data-level")?;
+                for ring in rings {
+                    let mut fluid = match Fluid::new(&ring) {
+                        Ok(fluid) => fluid,
+                        Err(e) => {
+                            let [x, y] = ring[0];
+                            skipped.push(format!(
+                                "skipped the <{}> at ({:.0}, {:.0}): {}",
+                                name, x, y, e
+                            ));
+                            continue;
+                        }
+                    };
+                    if let Some(density) = density {
+                        fluid = fluid.with_density(density);
+                    }
+                    if let Some(viscosity) = viscosity {
+                        fluid = fluid.with_viscosity(viscosity);
+                    }
+                    if let Some(level) = level {
+                        fluid = fluid.with_level(level);
+                    }
+                    collection.add_fluid(fluid);
+                }
+            }
+            Layer::Bodies => {
+                let color = attribute("fill")
+                    .or_else(|| style_value(attribute("style")?, "fill"))
+                    .and_then(parse_color);
+                for shape in nest(rings, color) {
+                    match shape_to_softbody(&shape, options.spacing) {
+                        Ok(body) => collection.add(body),
+                        Err(e) => {
+                            let [x, y] = shape.outline[0];
+                            skipped.push(format!(
+                                "skipped the <{}> at ({:.0}, {:.0}): {}",
+                                name, x, y, e
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((collection, skipped))
+}
+
+fn shape_to_softbody(shape: &Shape, spacing: f64) -> Result<SoftBody, String> {
+    let body = Mesh::from_polygon(&shape.outline, &shape.holes, spacing)?.to_softbody();
+    Ok(match shape.color {
+        Some(color) => body.with_color(color),
+        None => body,
+    })
+}
+
+/// Groups the subpaths of one element into shapes, treating rings that sit
+/// inside another ring as its holes.
+fn nest(rings: Vec<Vec<[f64; 2]>>, color: Option<[f32; 4]>) -> Vec<Shape> {
+    let inside_another = |i: usize| {
+        rings
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && geometry::contains(other, rings[i][0]))
+    };
+    let (holes, outlines): (Vec<usize>, Vec<usize>) =
+        (0..rings.len()).partition(|&i| inside_another(i));
+
+    outlines
+        .iter()
+        .map(|&i| Shape {
+            outline: rings[i].clone(),
+            holes: holes
+                .iter()
+                .filter(|&&h| geometry::contains(&rings[i], rings[h][0]))
+                .map(|&h| rings[h].clone())
+                .collect(),
+            color,
+        })
+        .collect()
+}
+
+/// `key="value"` pairs of a tag, quoted with either kind of quote.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq]
+            .split_whitespace()
+            .last()
+            .unwrap_or("")
+            .to_string();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = after[1..].find(quote) else {
+            break;
+        };
+        attributes.push((key, after[1..end + 1].to_string()));
+        rest = &after[end + 2..];
+    }
+    attributes
+}
+
+fn style_value<'a>(style: &'a str, key: &str) -> Option<&'a str> {
+    style
+        .split(';')
+        .filter_map(|entry| entry.split_once(':'))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim())
+}
+
+/// `#rgb` or `#rrggbb`; named colours and `none` are left to the default.
+fn parse_color(value: &str) -> Option<[f32; 4]> {
+    let hex = value.strip_prefix('#')?;
+    let digits: Vec<u8> = match hex.len() {
+        3 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8 * 17))
+            .collect::<Option<_>>()?,
+        6 => (0..3)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some([
+        digits[0] as f32 / 255.0,
+        digits[1] as f32 / 255.0,
+        digits[2] as f32 / 255.0,
+        1.0,
+    ])
+}
+
+fn apply(t: Transform, p: [f64; 2]) -> [f64; 2] {
+    [
+        t[0] * p[0] + t[2] * p[1] + t[4],
+        t[1] * p[0] + t[3] * p[1] + t[5],
+    ]
+}
+
+/// The transform that applies `inner` first, then `outer`.
+fn compose(outer: Transform, inner: Transform) -> Transform {
+    [
+        outer[0] * inner[0] + outer[2] * inner[1],
+        outer[1] * inner[0] + outer[3] * inner[1],
+        outer[0] * inner[2] + outer[2] * inner[3],
+        outer[1] * inner[2] + outer[3] * inner[3],
+        outer[0] * inner[4] + outer[2] * inner[5] + outer[4],
+        outer[1] * inner[4] + outer[3] * inner[5] + outer[5],
+    ]
+}
+
+/// Average stretch of a transform, for converting lengths between spaces.
+fn scale_of(t: Transform) -> f64 {
+    (t[0] * t[3] - t[1] * t[2]).abs().sqrt()
+}
+
+/// A transform list such as `translate(10,20) scale(2)`.
+fn parse_transform(value: &str) -> Result<Transform, String> {
+    let mut transform = IDENTITY;
+    let mut rest = value;
+    while let Some(open) = rest.find('(') {
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let close = rest[open..]
+            .find(')')
+            .ok_or_else(|| format!("unterminated transform '{}' in svg", value))?;
+        let args = parse_numbers(&rest[open + 1..open + close])?;
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        let next = match name {
+            "translate" => [1.0, 0.0, 0.0, 1.0, arg(0), arg(1)],
+            "scale" => {
+                let sy = args.get(1).copied().unwrap_or(arg(0));
+                [arg(0), 0.0, 0.0, sy, 0.0, 0.0]
+            }
+            "rotate" => {
+                let (sin, cos) = arg(0).to_radians().sin_cos();
+                let (cx, cy) = (arg(1), arg(2));
+                let rotation = [cos, sin, -sin, cos, 0.0, 0.0];
+                compose(
+                    [1.0, 0.0, 0.0, 1.0, cx, cy],
+                    compose(rotation, [1.0, 0.0, 0.0, 1.0, -cx, -cy]),
+                )
+            }
+            "matrix" if args.len() == 6 => [args[0], args[1], args[2], args[3], args[4], args[5]],
+            _ => return Err(format!("unsupported transform '{}' in svg", name)),
+        };
+        transform = compose(transform, next);
+        rest = &rest[open + close + 1..];
+    }
+    Ok(transform)
+}
+
+/// Numbers separated by commas or whitespace, including the compact forms
+/// SVG allows such as `1-2` and `.5.5`.
+fn parse_numbers(value: &str) -> Result<Vec<f64>, String> {
+    let mut numbers = Vec::new();
+    for token in Tokens::new(value) {
+        match token {
+            Token::Number(n) if n.is_finite() => numbers.push(n),
+            Token::Number(_) => return Err(format!("invalid number in svg '{}'", value)),
+            Token::Command(c) => return Err(format!("unexpected '{}' in svg numbers", c)),
+        }
+    }
+    Ok(numbers)
+}
+
+enum Token {
+    Command(char),
+    Number(f64),
+}
+
+struct Tokens<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    source: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str) -> Self {
+        Tokens {
+            chars: source.char_indices().peekable(),
+            source,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| c.is_whitespace() || *c == ',')
+            .is_some()
+        {}
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.skip_separators();
+        let (start, first) = self.chars.next()?;
+        if first.is_ascii_alphabetic() {
+            return Some(Token::Command(first));
+        }
+
+        let mut end = start + first.len_utf8();
+        let mut seen_dot = first == '.';
+        let mut seen_exponent = false;
+        while let Some(&(i, c)) = self.chars.peek() {
+            let previous = self.source[..i].chars().last();
+            let accept = c.is_ascii_digit()
+                || (c == '.' && !seen_dot && !seen_exponent)
+                || ((c == 'e' || c == 'E') && !seen_exponent)
+                || ((c == '-' || c == '+') && matches!(previous, Some('e' | 'E')));
+            if !accept {
+                break;
+            }
+            seen_dot |= c == '.';
+            seen_exponent |= c == 'e' || c == 'E';
+            end = i + c.len_utf8();
+            self.chars.next();
+        }
+        // Anything unparsable, like a lone '-', becomes NaN for the caller to reject
+        Some(Token::Number(
+            self.source[start..end].parse().unwrap_or(f64::NAN),
+        ))
+    }
+}
+
+/// Flattens path data into its closed subpaths; open subpaths are dropped
+/// since they enclose nothing.
+fn parse_path(d: &str, tolerance: f64) -> Result<Vec<Vec<[f64; 2]>>, String> {
+    let mut tokens = Tokens::new(d).peekable();
+    let mut rings = Vec::new();
+    let mut current: Vec<[f64; 2]> = Vec::new();
+    let mut pen = [0.0, 0.0];
+    let mut start = [0.0, 0.0];
+    // reflected control point for the smooth curve commands S and T
+    let mut last_control: Option<(char, [f64; 2])> = None;
+    let mut command = None;
+
+    loop {
+        let c = match tokens.peek() {
+            None => break,
+            Some(Token::Command(c)) => {
+                let c = *c;
+                tokens.next();
+                c
+            }
+            // Numbers after a command repeat it; after a move they are lines
+            Some(Token::Number(_)) => match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) => c,
+                None => return Err("path data must start with a command".to_string()),
+            },
+        };
+        command = Some(c);
+
+        let relative = c.is_ascii_lowercase();
+        let mut number = || match tokens.next() {
+            Some(Token::Number(n)) if n.is_finite() => Ok(n),
+            _ => Err(format!("missing or invalid number after '{}' in path", c)),
+        };
+        let origin = if relative { pen } else { [0.0, 0.0] };
+
+        match c.to_ascii_uppercase() {
+            'M' => {
+                current.clear();
+                pen = [origin[0] + number()?, origin[1] + number()?];
+                start = pen;
+                current.push(pen);
+            }
+            'L' => {
+                pen = [origin[0] + number()?, origin[1] + number()?];
+                current.push(pen);
+            }
+            'H' => {
+                pen[0] = origin[0] + number()?;
+                current.push(pen);
+            }
+            'V' => {
+                pen[1] = origin[1] + number()?;
+                current.push(pen);
+            }
+            'C' | 'S' => {
+                let c1 = if c.eq_ignore_ascii_case(&'C') {
+                    [origin[0] + number()?, origin[1] + number()?]
+                } else {
+                    reflect(last_control, 'C', pen)
+                };
+                let c2 = [origin[0] + number()?, origin[1] + number()?];
+                let end = [origin[0] + number()?, origin[1] + number()?];
+                flatten_cubic(pen, c1, c2, end, tolerance, &mut current);
+                pen = end;
+                last_control = Some(('C', c2));
+                continue;
+            }
+            'Q' | 'T' => {
+                let control = if c.eq_ignore_ascii_case(&'Q') {
+                    [origin[0] + number()?, origin[1] + number()?]
+                } else {
+                    reflect(last_control, 'Q', pen)
+                };
+                let end = [origin[0] + number()?, origin[1] + number()?];
+                // Raise to a cubic with the same shape
+                let c1 = [
+                    pen[0] + (control[0] - pen[0]) * 2.0 / 3.0,
+                    pen[1] + (control[1] - pen[1]) * 2.0 / 3.0,
+                ];
+                let c2 = [
+                    end[0] + (control[0] - end[0]) * 2.0 / 3.0,
+                    end[1] + (control[1] - end[1]) * 2.0 / 3.0,
+                ];
+                flatten_cubic(pen, c1, c2, end, tolerance, &mut current);
+                pen = end;
+                last_control = Some(('Q', control));
+                continue;
+            }
+            'A' => {
+                let radii = [number()?, number()?];
+                let rotation = number()?;
+                let (large_arc, sweep) = (number()? != 0.0, number()? != 0.0);
+                let end = [origin[0] + number()?, origin[1] + number()?];
+                current.extend(arc(pen, radii, rotation, large_arc, sweep, end, tolerance));
+                pen = end;
+            }
+            'Z' => {
+                if current.len() > 1 && current.first() == current.last() {
+                    current.pop();
+                }
+                rings.push(std::mem::take(&mut current));
+                pen = start;
+                current.push(pen);
+                // A lone Z has no arguments to repeat
+                command = None;
+            }
+            _ => return Err(format!("unsupported path command '{}'", c)),
+        }
+        last_control = None;
+    }
+
+    rings.retain(|ring| ring.len() >= 3);
+    Ok(rings)
+}
+
+/// Control point of a smooth curve: the last control point mirrored about the
+/// pen, or the pen itself if the previous segment wasn't the same kind of curve.
+fn reflect(last: Option<(char, [f64; 2])>, kind: char, pen: [f64; 2]) -> [f64; 2] {
+    match last {
+        Some((k, control)) if k == kind => [2.0 * pen[0] - control[0], 2.0 * pen[1] - control[1]],
+        _ => pen,
+    }
+}
+
+/// Appends points along a cubic Bézier (excluding `p0`) until every piece is
+/// within `tolerance` of the curve.
+fn flatten_cubic(
+    p0: [f64; 2],
+    p1: [f64; 2],
+    p2: [f64; 2],
+    p3: [f64; 2],
+    tolerance: f64,
+    out: &mut Vec<[f64; 2]>,
+) {
+    fn recurse(p: [[f64; 2]; 4], tolerance: f64, depth: u32, out: &mut Vec<[f64; 2]>) {
+        let flat = geometry::distance_to_segment(p[1], p[0], p[3])
+            .max(geometry::distance_to_segment(p[2], p[0], p[3]));
+        if flat <= tolerance || depth >= 16 {
+            out.push(p[3]);
+            return;
+        }
+        let mid = |a: [f64; 2], b: [f64; 2]| [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+        let (a, b, c) = (mid(p[0], p[1]), mid(p[1], p[2]), mid(p[2], p[3]));
+        let (d, e) = (mid(a, b), mid(b, c));
+        let f = mid(d, e);
+        recurse([p[0], a, d, f], tolerance, depth + 1, out);
+        recurse([f, e, c, p[3]], tolerance, depth + 1, out);
+    }
+    recurse([p0, p1, p2, p3], tolerance, 0, out);
+}
+
+/// Points along an elliptical arc given the way SVG paths do, from `p0` to
+/// `p1` (excluding `p0`), close enough that no chord strays past `tolerance`.
+/// Radii too small to reach `p1` are scaled up until they do.
+fn arc(
+    p0: [f64; 2],
+    radii: [f64; 2],
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    p1: [f64; 2],
+    tolerance: f64,
+) -> Vec<[f64; 2]> {
+    let (mut rx, mut ry) = (radii[0].abs(), radii[1].abs());
+    if rx == 0.0 || ry == 0.0 || p0 == p1 {
+        return vec![p1];
+    }
+
+    // Work in the ellipse's own frame, where the chord's midpoint is the origin
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((p0[0] - p1[0]) / 2.0, (p0[1] - p1[1]) / 2.0);
+    let (x, y) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let reach = (x / rx).powi(2) + (y / ry).powi(2);
+    if reach > 1.0 {
+        rx *= reach.sqrt();
+        ry *= reach.sqrt();
+    }
+
+    let (rx2, ry2, x2, y2) = (rx * rx, ry * ry, x * x, y * y);
+    let mut k = ((rx2 * ry2 - rx2 * y2 - ry2 * x2) / (rx2 * y2 + ry2 * x2))
+        .max(0.0)
+        .sqrt();
+    if large_arc == sweep {
+        k = -k;
+    }
+    let (cx, cy) = (k * rx * y / ry, -k * ry * x / rx);
+    let center = [
+        cos * cx - sin * cy + (p0[0] + p1[0]) / 2.0,
+        sin * cx + cos * cy + (p0[1] + p1[1]) / 2.0,
+    ];
+
+    let angle = |ux: f64, uy: f64| uy.atan2(ux);
+    let start = angle((x - cx) / rx, (y - cy) / ry);
+    let mut span = angle((-x - cx) / rx, (-y - cy) / ry) - start;
+    let tau = 2.0 * std::f64::consts::PI;
+    if sweep && span < 0.0 {
+        span += tau;
+    } else if !sweep && span > 0.0 {
+        span -= tau;
+    }
+
+    // A chord spanning angle θ sags by r(1 - cos(θ/2))
+    let r = rx.max(ry);
+    let step = 2.0 * (1.0 - (tolerance / r).min(1.0)).acos();
+    let segments = ((span.abs() / step.max(1e-3)).ceil() as usize).clamp(1, 256);
+    let mut points: Vec<[f64; 2]> = (1..segments)
+        .map(|i| {
+            let theta = start + span * i as f64 / segments as f64;
+            let (ex, ey) = (rx * theta.cos(), ry * theta.sin());
+            [
+                center[0] + cos * ex - sin * ey,
+                center[1] + sin * ex + cos * ey,
+            ]
+        })
+        .collect();
+    points.push(p1);
+    points
+}
+
+/// Samples an ellipse finely enough that no chord strays past `tolerance`.
+fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64, tolerance: f64) -> Vec<[f64; 2]> {
+    let r = rx.max(ry).max(f64::EPSILON);
+    // A chord spanning angle θ sags by r(1 - cos(θ/2))
+    let step = 2.0 * (1.0 - (tolerance / r).min(1.0)).acos();
+    let segments = ((2.0 * std::f64::consts::PI / step.max(1e-3)).ceil() as usize).clamp(8, 256);
+    (0..segments)
+        .map(|i| {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / segments as f64;
+            [cx + rx * angle.cos(), cy + ry * angle.sin()]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> SvgOptions {
+        SvgOptions {
+            scale: 1.0,
+            tolerance: 0.5,
+            spacing: 20.0,
+            static_layer: "static".to_string(),
+            fluid_layer: "fluid".to_string(),
+        }
+    }
+
+    #[test]
+    fn layers_and_transforms_place_shapes() {
+        let svg = r#"<svg>
+            <g id="static" transform="translate(10 20)">
+                <polygon points="0,0 100,0 100,50 0,0"/>
+            </g>
+            <g id="fluid"><rect width="200" height="100"/></g>
+            <circle cx="300" cy="300" r="40"/>
+        </svg>"#;
+        let (scene, _) = parse(svg, &options()).unwrap();
+        // The repeated closing point is dropped
+        assert_eq!(
+            scene.colliders[0].outline,
+            vec![[10.0, 20.0], [110.0, 20.0], [110.0, 70.0]]
+        );
+        assert_eq!(scene.fluids.len(), 1);
+        assert_eq!(scene.softbodies.len(), 1);
+    }
+
+    #[test]
+    fn definitions_and_hidden_layers_are_skipped() {
+        let svg = r#"<svg>
+            <defs><g><rect width="10" height="10"/></g></defs>
+            <g style="display: none"><circle r="5"/></g>
+            <rect display="none" width="10" height="10"/>
+            <rect x="50" width="10" height="10"/>
+        </svg>"#;
+        assert_eq!(parse(svg, &options()).unwrap().0.softbodies.len(), 1);
+    }
+
+    #[test]
+    fn scale_multiplies_every_coordinate() {
+        let svg = r#"<g id="static" transform="translate(10 -20)">
+            <polygon points="0,0 100,0 30,50"/>
+        </g>"#;
+        let (plain, _) = parse(svg, &options()).unwrap();
+        let (scaled, _) = parse(
+            svg,
+            &SvgOptions {
+                scale: 2.5,
+                ..options()
+            },
+        )
+        .unwrap();
+        let expected: Vec<[f64; 2]> = plain.colliders[0]
+            .outline
+            .iter()
+            .map(|p| [p[0] * 2.5, p[1] * 2.5])
+            .collect();
+        assert_eq!(scaled.colliders[0].outline, expected);
+    }
+
+    /// How far the curve strays from the flattened points: the worst distance
+    /// from a fine sampling of `curve` to the nearest segment.
+    fn flattening_error(curve: impl Fn(f64) -> [f64; 2], flat: &[[f64; 2]]) -> f64 {
+        (0..=1000)
+            .map(|i| {
+                let p = curve(i as f64 / 1000.0);
+                flat.windows(2)
+                    .map(|s| geometry::distance_to_segment(p, s[0], s[1]))
+                    .fold(f64::MAX, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn curves_are_flattened_within_tolerance() {
+        let p = [[0.0, 0.0], [30.0, 200.0], [170.0, -120.0], [200.0, 40.0]];
+        let cubic = |t: f64| {
+            let u = 1.0 - t;
+            let weights = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+            let mut point = [0.0, 0.0];
+            for (w, q) in weights.iter().zip(&p) {
+                point[0] += w * q[0];
+                point[1] += w * q[1];
+            }
+            point
+        };
+
+        let mut counts = Vec::new();
+        for tolerance in [2.0, 0.5, 0.1] {
+            let mut flat = vec![p[0]];
+            flatten_cubic(p[0], p[1], p[2], p[3], tolerance, &mut flat);
+            assert!(flattening_error(cubic, &flat) <= tolerance);
+            assert_eq!(flat.last(), Some(&p[3]));
+            counts.push(flat.len());
+        }
+        assert!(
+            counts[0] < counts[1] && counts[1] < counts[2],
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn arcs_follow_their_ellipse() {
+        // Two half turns around a circle of radius 50 centred on (50, 50)
+        let d = "M 0 50 A 50 50 0 1 1 100 50 A 50 50 0 1 1 0 50 Z";
+        let mut counts = Vec::new();
+        for tolerance in [1.0, 0.1] {
+            let rings = parse_path(d, tolerance).unwrap();
+            let mut ring = rings[0].clone();
+            for p in &ring {
+                assert!(((p[0] - 50.0).hypot(p[1] - 50.0) - 50.0).abs() < 1e-9);
+            }
+            ring.push(ring[0]);
+            let circle = |t: f64| {
+                let (sin, cos) = (t * std::f64::consts::TAU).sin_cos();
+                [50.0 + 50.0 * cos, 50.0 + 50.0 * sin]
+            };
+            assert!(flattening_error(circle, &ring) <= tolerance);
+            counts.push(ring.len());
+        }
+        assert!(counts[0] < counts[1]);
+
+        // The sweep flag picks which way round the arc goes; with y pointing
+        // down, a positive sweep bulges upward
+        let lowest = |d: &str| {
+            parse_path(d, 0.1).unwrap()[0]
+                .iter()
+                .map(|p| p[1])
+                .fold(f64::MAX, f64::min)
+        };
+        assert!(lowest("M 0 0 A 50 50 0 0 1 100 0 Z") < -49.0);
+        assert_eq!(lowest("M 0 0 A 50 50 0 0 0 100 0 Z"), 0.0);
+
+        // Radii too small to span the ends are grown until they do
+        let ring = &parse_path("M 0 0 A 1 1 0 0 1 100 0 Z", 0.1).unwrap()[0];
+        assert!(ring.len() > 10);
+        for p in ring {
+            assert!(((p[0] - 50.0).hypot(p[1]) - 50.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn concave_fluid_shapes_are_skipped() {
+        let svg = r#"<g id="fluid">
+            <polygon points="0,0 100,0 100,100 50,50 0,100"/>
+            <rect x="200" width="50" height="50"/>
+        </g>"#;
+        let (scene, skipped) = parse(svg, &options()).unwrap();
+        assert_eq!(scene.fluids.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("convex"), "{}", skipped[0]);
+    }
+
+    #[test]
+    fn shapes_that_fail_to_mesh_are_skipped() {
+        // The two holes cross each other, so the first shape can't be meshed
+        let svg = r#"<svg>
+            <path d="M0 0 H100 V100 H0 Z M20 20 H60 V60 H20 Z M45 47 H83 V81 H45 Z"/>
+            <rect x="200" width="50" height="50"/>
+        </svg>"#;
+        let (scene, skipped) = parse(svg, &options()).unwrap();
+        assert_eq!(scene.softbodies.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("<path>"), "{}", skipped[0]);
+    }
+
+    #[test]
+    fn shapes_without_a_size_are_rejected() {
+        for svg in [
+            r#"<circle cx="10" cy="10"/>"#,
+            r#"<rect width="10" height="0"/>"#,
+            r#"<ellipse rx="-3" ry="4"/>"#,
+        ] {
+            assert!(parse(svg, &options()).is_err(), "{}", svg);
+        }
+    }
+}
@@ -1,73 +1,383 @@
 use piston_window::*;
-use std::sync::mpsc::Sender;
 
-use crate::simulation::{self, SoftBodyCollection};
+// Immediate-mode widgets: panels and widgets are declared every frame between
+// `begin` and `end`, and each widget call returns whether the user changed it.
+// Drawing is queued and replayed inside `draw_2d` by `render`.
 
-pub struct Button {
-    position: [f64; 2],
-    size: [f64; 2],
-    color: [f32; 4],
-    label: String,
+const ROW_HEIGHT: f64 = 24.0;
+const SPACING: f64 = 4.0;
+const PADDING: f64 = 8.0;
+const FONT_SIZE: u32 = 14;
+
+const PANEL_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const WIDGET_COLOR: [f32; 4] = [0.25, 0.25, 0.35, 1.0];
+const HOVER_COLOR: [f32; 4] = [0.35, 0.35, 0.5, 1.0];
+const ACCENT_COLOR: [f32; 4] = [0.2, 0.6, 0.8, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Which window corner a panel's offset is measured from.
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
 }
 
-impl Button {
-    pub fn new(position: [f64; 2], size: [f64; 2], color: [f32; 4], label: String) -> Self {
-        Button {
-            position,
-            size,
-            color,
-            label,
+impl Anchor {
+    pub fn resolve(self, offset: [f64; 2], window_size: [f64; 2]) -> [f64; 2] {
+        match self {
+            Anchor::TopLeft => offset,
+            Anchor::TopRight => [window_size[0] - offset[0], offset[1]],
         }
     }
+}
 
-    pub fn render(&self, c: Context, g: &mut G2d, glyphs: &mut Glyphs) {
-        rectangle(
-            self.color,
-            [
-                self.position[0],
-                self.position[1],
-                self.size[0],
-                self.size[1],
-            ],
-            c.transform,
-            g,
-        );
-        text::Text::new_color([1.0, 1.0, 1.0, 1.0], 16)
-            .draw(
-                &self.label,
-                glyphs,
-                &c.draw_state,
-                c.transform.trans(self.position[0], self.position[1] + 50.0),
-                g,
-            )
-            .unwrap();
-    }
-
-    pub fn click_range(&self, mouse_pos: [f64; 2]) -> bool {
-        mouse_pos[0] >= self.position[0]
-            && mouse_pos[0] <= self.position[0] + self.size[0]
-            && mouse_pos[1] >= self.position[1]
-            && mouse_pos[1] <= self.position[1] + self.size[1]
-    }
-
-    pub fn handle_click_spawn(
-        &self,
-        mouse_pos: [f64; 2],
-        mut softbodies: SoftBodyCollection,
-        to_sim_tx: &Sender<simulation::SoftBodyCollection>,
-    ) {
-        if self.click_range(mouse_pos) {
-            let new_softbody = simulation::SoftBody::new_square([200.0, 100.0], 100.0, 4);
-            softbodies.add(new_softbody);
-            to_sim_tx.send(softbodies).unwrap();
-            println!("Spawned new softbody!");
+enum Draw {
+    Rect([f32; 4], [f64; 4]),
+    Text([f32; 4], [f64; 2], String),
+}
+
+struct Panel {
+    origin: [f64; 2],
+    width: f64,
+    cursor: f64,
+    // index in `draws` where the background goes once the height is known
+    background: usize,
+}
+
+pub struct Ui {
+    mouse_pos: [f64; 2],
+    mouse_down: bool,
+    // the mouse went down since the last frame and no widget has taken the click yet
+    pressed: bool,
+    window_size: [f64; 2],
+    // label of the slider being dragged
+    active: Option<String>,
+    open_dropdown: Option<String>,
+    panel: Option<Panel>,
+    draws: Vec<Draw>,
+    // drawn after everything else so open dropdowns sit on top
+    overlay: Vec<Draw>,
+    rects: Vec<[f64; 4]>,
+    last_rects: Vec<[f64; 4]>,
+}
+
+fn contains(rect: [f64; 4], pos: [f64; 2]) -> bool {
+    pos[0] >= rect[0]
+        && pos[0] <= rect[0] + rect[2]
+        && pos[1] >= rect[1]
+        && pos[1] <= rect[1] + rect[3]
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Ui {
+            mouse_pos: [0.0, 0.0],
+            mouse_down: false,
+            pressed: false,
+            window_size: [0.0, 0.0],
+            active: None,
+            open_dropdown: None,
+            panel: None,
+            draws: Vec::new(),
+            overlay: Vec::new(),
+            rects: Vec::new(),
+            last_rects: Vec::new(),
+        }
+    }
+
+    pub fn handle_mouse_move(&mut self, pos: [f64; 2]) {
+        self.mouse_pos = pos;
+    }
+
+    pub fn handle_mouse_down(&mut self) {
+        self.mouse_down = true;
+        self.pressed = true;
+    }
+
+    pub fn handle_mouse_up(&mut self) {
+        self.mouse_down = false;
+        self.active = None;
+    }
+
+    /// True if the cursor is over the UI, so clicks shouldn't reach the scene.
+    pub fn wants_mouse(&self) -> bool {
+        self.active.is_some()
+            || self.open_dropdown.is_some()
+            || self.last_rects.iter().any(|r| contains(*r, self.mouse_pos))
+    }
+
+    pub fn begin(&mut self, window_size: [f64; 2]) {
+        self.window_size = window_size;
+        self.draws.clear();
+        self.overlay.clear();
+        self.rects.clear();
+    }
+
+    pub fn end(&mut self) {
+        // A click that no widget used closes any open dropdown
+        if self.pressed {
+            self.open_dropdown = None;
+        }
+        self.pressed = false;
+        std::mem::swap(&mut self.rects, &mut self.last_rects);
+    }
+
+    pub fn begin_panel(&mut self, anchor: Anchor, offset: [f64; 2], width: f64) {
+        let origin = anchor.resolve(offset, self.window_size);
+        self.panel = Some(Panel {
+            origin,
+            width,
+            cursor: origin[1] + PADDING,
+            background: self.draws.len(),
+        });
+        self.draws.push(Draw::Rect(PANEL_COLOR, [0.0; 4]));
+    }
+
+    pub fn end_panel(&mut self) {
+        if let Some(panel) = self.panel.take() {
+            let rect = [
+                panel.origin[0],
+                panel.origin[1],
+                panel.width,
+                panel.cursor - panel.origin[1] + PADDING - SPACING,
+            ];
+            self.draws[panel.background] = Draw::Rect(PANEL_COLOR, rect);
+            self.rects.push(rect);
         }
     }
 
-    pub fn handle_click_pause(&self, mouse_pos: [f64; 2], pause: bool) -> bool {
-        if self.click_range(mouse_pos) {
-            return !pause;
+    /// Reserves the next row of the current panel.
+    fn row(&mut self) -> [f64; 4] {
+        let panel = self.panel.as_mut().expect("widgets must be inside a panel");
+        let rect = [
+            panel.origin[0] + PADDING,
+            panel.cursor,
+            panel.width - PADDING * 2.0,
+            ROW_HEIGHT,
+        ];
+        panel.cursor += ROW_HEIGHT + SPACING;
+        rect
+    }
+
+    fn hovered(&self, rect: [f64; 4]) -> bool {
+        contains(rect, self.mouse_pos)
+    }
+
+    /// Takes the pending click if it landed in `rect`.
+    fn click(&mut self, rect: [f64; 4]) -> bool {
+        if self.pressed && self.hovered(rect) {
+            self.pressed = false;
+            return true;
         }
-        pause
+        false
+    }
+
+    fn text_draw(rect: [f64; 4], text: String) -> Draw {
+        let baseline = rect[1] + (rect[3] + FONT_SIZE as f64) / 2.0 - 2.0;
+        Draw::Text(TEXT_COLOR, [rect[0] + 4.0, baseline], text)
+    }
+
+    pub fn label(&mut self, text: &str) {
+        let rect = self.row();
+        self.draws.push(Ui::text_draw(rect, text.to_string()));
+    }
+
+    pub fn button(&mut self, label: &str) -> bool {
+        let rect = self.row();
+        let color = if self.hovered(rect) {
+            HOVER_COLOR
+        } else {
+            WIDGET_COLOR
+        };
+        self.draws.push(Draw::Rect(color, rect));
+        self.draws.push(Ui::text_draw(rect, label.to_string()));
+        self.click(rect)
+    }
+
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let rect = self.row();
+        let size = ROW_HEIGHT - 8.0;
+        let boxed = [rect[0], rect[1] + 4.0, size, size];
+        self.draws.push(Draw::Rect(WIDGET_COLOR, boxed));
+        if *value {
+            self.draws.push(Draw::Rect(
+                ACCENT_COLOR,
+                [boxed[0] + 3.0, boxed[1] + 3.0, size - 6.0, size - 6.0],
+            ));
+        }
+        self.draws.push(Ui::text_draw(
+            [rect[0] + size + 2.0, rect[1], rect[2], rect[3]],
+            label.to_string(),
+        ));
+
+        let clicked = self.click(rect);
+        if clicked {
+            *value = !*value;
+        }
+        clicked
+    }
+
+    pub fn slider(&mut self, label: &str, value: &mut f64, min: f64, max: f64) -> bool {
+        let rect = self.row();
+        if self.click(rect) {
+            self.active = Some(label.to_string());
+        }
+
+        let old = *value;
+        if self.mouse_down && self.active.as_deref() == Some(label) {
+            let t = ((self.mouse_pos[0] - rect[0]) / rect[2]).clamp(0.0, 1.0);
+            *value = min + (max - min) * t;
+        }
+
+        let t = ((*value - min) / (max - min)).clamp(0.0, 1.0);
+        self.draws.push(Draw::Rect(WIDGET_COLOR, rect));
+        self.draws.push(Draw::Rect(
+            ACCENT_COLOR,
+            [rect[0], rect[1], rect[2] * t, rect[3]],
+        ));
+        self.draws
+            .push(Ui::text_draw(rect, format!("{}: {:.3}", label, value)));
+        *value != old
+    }
+
+    /// Shows the selected option; clicking it lists every option below it.
+    pub fn dropdown(&mut self, label: &str, selected: &mut usize, options: &[&str]) -> bool {
+        let rect = self.row();
+        let open = self.open_dropdown.as_deref() == Some(label);
+
+        // Options are handled here, before any widget declared later that they
+        // cover, so those widgets never see the click
+        let mut changed = false;
+        if open {
+            for (i, option) in options.iter().enumerate() {
+                let item = [
+                    rect[0],
+                    rect[1] + ROW_HEIGHT * (i + 1) as f64,
+                    rect[2],
+                    ROW_HEIGHT,
+                ];
+                let color = if self.hovered(item) || i == *selected {
+                    HOVER_COLOR
+                } else {
+                    WIDGET_COLOR
+                };
+                self.overlay.push(Draw::Rect(color, item));
+                self.overlay.push(Ui::text_draw(item, option.to_string()));
+                self.rects.push(item);
+
+                if self.click(item) {
+                    changed = *selected != i;
+                    *selected = i;
+                    self.open_dropdown = None;
+                }
+            }
+        }
+
+        self.draws.push(Draw::Rect(WIDGET_COLOR, rect));
+        let current = options.get(*selected).copied().unwrap_or("");
+        self.draws
+            .push(Ui::text_draw(rect, format!("{}: {}", label, current)));
+        if self.click(rect) {
+            self.open_dropdown = if open { None } else { Some(label.to_string()) };
+        }
+        changed
+    }
+
+    pub fn render(&self, c: Context, g: &mut G2d, glyphs: &mut Glyphs) {
+        for draw in self.draws.iter().chain(&self.overlay) {
+            match draw {
+                Draw::Rect(color, rect) => rectangle(*color, *rect, c.transform, g),
+                Draw::Text(color, pos, text) => {
+                    text::Text::new_color(*color, FONT_SIZE)
+                        .draw(
+                            text,
+                            glyphs,
+                            &c.draw_state,
+                            c.transform.trans(pos[0], pos[1]),
+                            g,
+                        )
+                        .unwrap();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows of a panel at the window's corner start 8 in and are 24 tall, every 28
+    fn frame(ui: &mut Ui, widgets: impl FnOnce(&mut Ui)) {
+        ui.begin([800.0, 600.0]);
+        ui.begin_panel(Anchor::TopLeft, [0.0, 0.0], 116.0);
+        widgets(ui);
+        ui.end_panel();
+        ui.end();
+    }
+
+    #[test]
+    fn sliders_follow_the_mouse_while_held() {
+        let mut ui = Ui::new();
+        let mut value = 0.0;
+        ui.handle_mouse_move([58.0, 20.0]);
+        ui.handle_mouse_down();
+        frame(&mut ui, |ui| {
+            assert!(ui.slider("Size", &mut value, 0.0, 10.0))
+        });
+        assert_eq!(value, 5.0);
+
+        ui.handle_mouse_move([500.0, 300.0]);
+        frame(&mut ui, |ui| {
+            assert!(ui.slider("Size", &mut value, 0.0, 10.0))
+        });
+        assert_eq!(value, 10.0);
+
+        ui.handle_mouse_up();
+        ui.handle_mouse_move([8.0, 20.0]);
+        frame(&mut ui, |ui| {
+            assert!(!ui.slider("Size", &mut value, 0.0, 10.0))
+        });
+        assert_eq!(value, 10.0);
+    }
+
+    #[test]
+    fn open_dropdowns_take_clicks_from_widgets_below() {
+        let mut ui = Ui::new();
+        let mut selected = 2;
+        let options = ["A", "B", "C"];
+        ui.handle_mouse_move([20.0, 20.0]);
+        ui.handle_mouse_down();
+        ui.handle_mouse_up();
+        frame(&mut ui, |ui| {
+            ui.dropdown("Pick", &mut selected, &options);
+            ui.button("Below");
+        });
+        assert!(ui.wants_mouse());
+
+        // The first option covers the button's row
+        ui.handle_mouse_move([20.0, 45.0]);
+        ui.handle_mouse_down();
+        frame(&mut ui, |ui| {
+            assert!(ui.dropdown("Pick", &mut selected, &options));
+            assert!(!ui.button("Below"));
+        });
+        assert_eq!(selected, 0);
+    }
+
+    #[test]
+    fn anchors_follow_the_window_size() {
+        assert_eq!(
+            Anchor::TopLeft.resolve([130.0, 30.0], [1024.0, 768.0]),
+            [130.0, 30.0]
+        );
+        assert_eq!(
+            Anchor::TopRight.resolve([130.0, 30.0], [800.0, 600.0]),
+            [670.0, 30.0]
+        );
+        assert_eq!(
+            Anchor::TopRight.resolve([130.0, 30.0], [1024.0, 768.0]),
+            [894.0, 30.0]
+        );
     }
 }